@@ -1,5 +1,6 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use emo_audio::stft::*;
+use ndarray::Array2;
 use rand::distributions::Uniform;
 use rand::prelude::*;
 
@@ -15,7 +16,7 @@ pub fn stft_benchmark(c: &mut Criterion) {
     for _ in 0..(8 * KB) {
         signal.push(rng.sample(side));
     }
-    let stft = ShortTimeFourierTransform::default();
+    let mut stft = ShortTimeFourierTransform::default();
     let mut group = c.benchmark_group("stft");
     for size in [KB / 4, KB / 2, KB, 2 * KB, 4 * KB, 8 * KB].iter() {
         group.throughput(Throughput::Bytes(*size as u64));
@@ -24,6 +25,16 @@ pub fn stft_benchmark(c: &mut Criterion) {
         });
     }
     group.finish();
+
+    let mut group = c.benchmark_group("stft_into");
+    for size in [KB / 4, KB / 2, KB, 2 * KB, 4 * KB, 8 * KB].iter() {
+        let mut out = Array2::zeros((1 + stft.n_fft() / 2, stft.frame_count(*size)));
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+            b.iter(|| stft.run_into(&signal[0..size], out.view_mut()));
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, stft_benchmark);
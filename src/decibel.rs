@@ -0,0 +1,174 @@
+use crate::error::Error;
+use ndarray::{prelude::*, Data};
+use num_traits::Float;
+
+/// Reference the spectrogram is scaled relative to when converting to
+/// decibels, matching the `ref` argument of the librosa functions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DbReference<F = f32> {
+    /// Scale relative to a fixed value, librosa uses 1.0 by default
+    Value(F),
+    /// Scale relative to the peak of the spectrogram so the loudest bin is 0dB,
+    /// the same as passing `ref=np.max` to librosa
+    Max,
+}
+
+impl<F: Float> Default for DbReference<F> {
+    fn default() -> Self {
+        Self::Value(F::one())
+    }
+}
+
+/// Convert a power spectrogram to decibels as `librosa.power_to_db` does,
+/// computing `10 * log10(max(S, amin) / max(ref, amin))`. If `top_db` is
+/// provided the output is clipped to no less than `top_db` below the peak. The
+/// librosa defaults are a reference of 1.0, an `amin` of `1e-10` and a `top_db`
+/// of 80.0.
+///
+/// Returns an error if `amin` isn't positive or `top_db` is negative
+pub fn power_to_db<S, D, F>(
+    spectrogram: &ArrayBase<S, D>,
+    reference: DbReference<F>,
+    amin: F,
+    top_db: Option<F>,
+) -> Result<Array<F, D>, Error>
+where
+    S: Data<Elem = F>,
+    D: Dimension,
+    F: Float,
+{
+    if amin <= F::zero() {
+        return Err(Error::InvalidParameter("amin must be positive".to_string()));
+    }
+    if top_db.map(|x| x < F::zero()).unwrap_or(false) {
+        return Err(Error::InvalidParameter(
+            "top_db must be non-negative".to_string(),
+        ));
+    }
+    let ten = F::from(10.0).unwrap();
+    let ref_value = match reference {
+        DbReference::Value(x) => x.abs(),
+        DbReference::Max => max(spectrogram),
+    };
+    let offset = ten * ref_value.max(amin).log10();
+    let mut db = spectrogram.mapv(|x| ten * x.max(amin).log10() - offset);
+    if let Some(top_db) = top_db {
+        let floor = max(&db) - top_db;
+        db.mapv_inplace(|x| x.max(floor));
+    }
+    Ok(db)
+}
+
+/// Convert an amplitude spectrogram to decibels as `librosa.amplitude_to_db`
+/// does. This is `power_to_db` of the squared amplitudes with the reference
+/// and `amin` squared. The librosa defaults are a reference of 1.0, an `amin`
+/// of `1e-5` and a `top_db` of 80.0.
+///
+/// Returns an error if `amin` isn't positive or `top_db` is negative
+pub fn amplitude_to_db<S, D, F>(
+    spectrogram: &ArrayBase<S, D>,
+    reference: DbReference<F>,
+    amin: F,
+    top_db: Option<F>,
+) -> Result<Array<F, D>, Error>
+where
+    S: Data<Elem = F>,
+    D: Dimension,
+    F: Float,
+{
+    let magnitude = spectrogram.mapv(F::abs);
+    let ref_value = match reference {
+        DbReference::Value(x) => x.abs(),
+        DbReference::Max => max(&magnitude),
+    };
+    let power = magnitude.mapv(|x| x * x);
+    power_to_db(
+        &power,
+        DbReference::Value(ref_value * ref_value),
+        amin * amin,
+        top_db,
+    )
+}
+
+/// Convert a spectrogram in decibels back to power as `librosa.db_to_power`
+/// does, computing `ref * 10^(S_db / 10)`
+pub fn db_to_power<S, D, F>(spectrogram: &ArrayBase<S, D>, reference: F) -> Array<F, D>
+where
+    S: Data<Elem = F>,
+    D: Dimension,
+    F: Float,
+{
+    let ten = F::from(10.0).unwrap();
+    spectrogram.mapv(|x| reference * ten.powf(x / ten))
+}
+
+/// Convert a spectrogram in decibels back to amplitude as
+/// `librosa.db_to_amplitude` does, computing `ref * 10^(S_db / 20)`
+pub fn db_to_amplitude<S, D, F>(spectrogram: &ArrayBase<S, D>, reference: F) -> Array<F, D>
+where
+    S: Data<Elem = F>,
+    D: Dimension,
+    F: Float,
+{
+    let ten = F::from(10.0).unwrap();
+    let twenty = F::from(20.0).unwrap();
+    spectrogram.mapv(|x| reference * ten.powf(x / twenty))
+}
+
+fn max<S, D, F>(x: &ArrayBase<S, D>) -> F
+where
+    S: Data<Elem = F>,
+    D: Dimension,
+    F: Float,
+{
+    x.fold(F::neg_infinity(), |acc, x| acc.max(*x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn decibel_conversions() {
+        // Generated via librosa.power_to_db(S, ref=..., amin=..., top_db=...)
+        let power = array![[1.0, 10.0, 100.0], [0.0, 1e-3, 1e-12]];
+        let db = power_to_db(&power, DbReference::default(), 1e-10, Some(80.0)).unwrap();
+        let expected = array![[0.0, 10.0, 20.0], [-60.0, -30.0, -60.0]];
+        for (a, e) in db.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+
+        let db = power_to_db(&power, DbReference::Max, 1e-10, None).unwrap();
+        let expected = array![[-20.0, -10.0, 0.0], [-120.0, -50.0, -120.0]];
+        for (a, e) in db.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+
+        let amplitude = array![[1.0f32, -10.0], [0.1, 0.0]];
+        let db = amplitude_to_db(&amplitude, DbReference::Value(2.0), 1e-5, Some(80.0)).unwrap();
+        let expected = array![[-6.0206, 13.9794], [-26.0206, -66.0206]];
+        for (a, e) in db.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-4), "{} ~= {}", a, e);
+        }
+
+        assert!(power_to_db(&power, DbReference::Max, 0.0, None).is_err());
+        assert!(power_to_db(&power, DbReference::Max, 1e-10, Some(-1.0)).is_err());
+    }
+
+    #[test]
+    fn inverse_conversions() {
+        let power = array![0.5, 1.0, 2.0, 1000.0];
+        let db = power_to_db(&power, DbReference::Value(2.0), 1e-10, None).unwrap();
+        let recon = db_to_power(&db, 2.0);
+        for (a, e) in recon.iter().zip(power.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+
+        let db = amplitude_to_db(&power, DbReference::Value(2.0), 1e-5, None).unwrap();
+        let recon = db_to_amplitude(&db, 2.0);
+        for (a, e) in recon.iter().zip(power.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::error::Error;
+use crate::stft::StftFloat;
+use ndarray::{prelude::*, Data};
+
+/// How a Savitzky-Golay delta is extended past the edges of the features.
+/// These are the `mode` argument of `librosa.feature.delta`, which is passed
+/// on to `scipy.signal.savgol_filter`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeltaMode<F = f32> {
+    /// Fit a polynomial to the first and last windows and take its derivative
+    /// at the edge frames. This is the librosa default and needs at least
+    /// `width` frames
+    Interp,
+    /// Reflect about the edge frames without repeating them, `d c b | a b c d`
+    Mirror,
+    /// Pad with a constant value, librosa uses the `cval` of 0.0
+    Constant(F),
+    /// Repeat the edge frames
+    Nearest,
+    /// Wrap around to the other end of the features
+    Wrap,
+}
+
+/// Computes delta features, the local derivative of a feature matrix such as
+/// MFCCs along its frames. Stacking the features with their first and second
+/// order deltas gives the common MFCC + delta + delta-delta features
+pub trait DeltasExt<F = f32> {
+    /// Compute the deltas as `librosa.feature.delta` does, by fitting a
+    /// polynomial of degree `order` to `width` consecutive values along `axis`
+    /// and taking the `order`th derivative. The width must be odd and at
+    /// least 3 and librosa uses a width of 9, an order of 1 and `Axis(1)` for
+    /// the `(features, frames)` arrays produced by this crate
+    fn delta(
+        &self,
+        width: usize,
+        order: usize,
+        axis: Axis,
+        mode: DeltaMode<F>,
+    ) -> Result<Array2<F>, Error>;
+
+    /// Compute the deltas as `python_speech_features.delta` does, with the
+    /// regression formula over the `n` values either side along `axis`. The
+    /// edge values are repeated to fill the regression window. For the
+    /// `(features, frames)` arrays produced by this crate use `Axis(1)`
+    fn regression_delta(&self, n: usize, axis: Axis) -> Result<Array2<F>, Error>;
+}
+
+impl<S, F> DeltasExt<F> for ArrayBase<S, Ix2>
+where
+    S: Data<Elem = F>,
+    F: StftFloat,
+{
+    fn delta(
+        &self,
+        width: usize,
+        order: usize,
+        axis: Axis,
+        mode: DeltaMode<F>,
+    ) -> Result<Array2<F>, Error> {
+        if width < 3 || width % 2 != 1 {
+            return Err(Error::InvalidParameter(
+                "width must be an odd integer of at least 3".to_string(),
+            ));
+        }
+        if order == 0 || order >= width {
+            return Err(Error::InvalidParameter(format!(
+                "order must be between 1 and the width {}",
+                width - 1
+            )));
+        }
+        let len = self.len_of(axis);
+        if matches!(mode, DeltaMode::Interp) && width > len {
+            return Err(Error::InvalidParameter(format!(
+                "width {} is greater than the {} values along the axis, reduce the width or use another mode",
+                width, len
+            )));
+        }
+
+        let half = width / 2;
+        let offsets = (0..width)
+            .map(|k| k as f64 - half as f64)
+            .collect::<Vec<_>>();
+        let coeffs = savgol_weights::<F>(&offsets, order, order);
+        // The edge frames are replaced by the derivative of the polynomial fit
+        // to the first and last windows
+        let edge_weights = |centre: usize| {
+            let offsets = (0..width)
+                .map(|t| t as f64 - centre as f64)
+                .collect::<Vec<_>>();
+            savgol_weights::<F>(&offsets, order, order)
+        };
+        let (start, end) = if matches!(mode, DeltaMode::Interp) {
+            (
+                (0..half).map(edge_weights).collect::<Vec<_>>(),
+                (width - half..width).map(edge_weights).collect::<Vec<_>>(),
+            )
+        } else {
+            (vec![], vec![])
+        };
+
+        let mut result = Array2::zeros(self.raw_dim());
+        for (lane, mut out) in self.lanes(axis).into_iter().zip(result.lanes_mut(axis)) {
+            for (n, y) in out.iter_mut().enumerate() {
+                *y = coeffs.iter().enumerate().fold(F::zero(), |acc, (k, c)| {
+                    acc + *c * extend(&lane, n as isize + k as isize - half as isize, mode)
+                });
+            }
+            for (i, weights) in start.iter().enumerate() {
+                out[i] = dot(weights, lane.slice(s![..width]));
+            }
+            for (i, weights) in end.iter().enumerate() {
+                out[len - half + i] = dot(weights, lane.slice(s![len - width..]));
+            }
+        }
+        Ok(result)
+    }
+
+    fn regression_delta(&self, n: usize, axis: Axis) -> Result<Array2<F>, Error> {
+        if n == 0 {
+            return Err(Error::InvalidParameter("n must be at least 1".to_string()));
+        }
+        let denominator = F::from_usize(2 * (1..=n).map(|i| i * i).sum::<usize>()).unwrap();
+        let mut result = Array2::zeros(self.raw_dim());
+        for (lane, mut out) in self.lanes(axis).into_iter().zip(result.lanes_mut(axis)) {
+            for (t, y) in out.iter_mut().enumerate() {
+                let t = t as isize;
+                *y = (1..=n as isize).fold(F::zero(), |acc, i| {
+                    let diff = extend(&lane, t + i, DeltaMode::Nearest)
+                        - extend(&lane, t - i, DeltaMode::Nearest);
+                    acc + F::from_isize(i).unwrap() * diff
+                }) / denominator;
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Weights giving the `deriv`th derivative at zero of the least squares
+/// polynomial of degree `order` through samples at the given offsets. This is
+/// the minimum norm solution to `A c = deriv! e_deriv` with `A_ij = x_j^i`,
+/// the same as `scipy.signal.savgol_coeffs`
+fn savgol_weights<F: StftFloat>(offsets: &[f64], order: usize, deriv: usize) -> Vec<F> {
+    let n = order + 1;
+    let powers = (0..n)
+        .map(|i| offsets.iter().map(|x| x.powi(i as i32)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // Solve (A A^T) z = deriv! e_deriv by gaussian elimination with partial
+    // pivoting, the system is tiny so this is plenty accurate
+    let mut system = (0..n)
+        .map(|i| {
+            let mut row = (0..n)
+                .map(|j| {
+                    powers[i]
+                        .iter()
+                        .zip(powers[j].iter())
+                        .map(|(a, b)| a * b)
+                        .sum::<f64>()
+                })
+                .collect::<Vec<_>>();
+            row.push(if i == deriv {
+                (1..=deriv).map(|k| k as f64).product()
+            } else {
+                0.0
+            });
+            row
+        })
+        .collect::<Vec<_>>();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|a, b| system[*a][col].abs().total_cmp(&system[*b][col].abs()))
+            .unwrap_or(col);
+        system.swap(col, pivot);
+        let (upper, lower) = system.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower.iter_mut() {
+            let factor = row[col] / pivot_row[col];
+            for (x, p) in row[col..].iter_mut().zip(pivot_row[col..].iter()) {
+                *x -= factor * p;
+            }
+        }
+    }
+    let mut z = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).map(|k| system[row][k] * z[k]).sum::<f64>();
+        z[row] = (system[row][n] - sum) / system[row][row];
+    }
+    (0..offsets.len())
+        .map(|j| F::from_f64((0..n).map(|i| powers[i][j] * z[i]).sum()).unwrap())
+        .collect()
+}
+
+/// Get the value at `index` of the lane, extending it past the edges with
+/// the given mode. Interp uses zeros as the edges are fit separately
+fn extend<F: StftFloat>(lane: &ArrayView1<F>, index: isize, mode: DeltaMode<F>) -> F {
+    let len = lane.len() as isize;
+    if (0..len).contains(&index) {
+        return lane[index as usize];
+    }
+    let index = match mode {
+        DeltaMode::Interp => return F::zero(),
+        DeltaMode::Constant(value) => return value,
+        DeltaMode::Nearest => index.clamp(0, len - 1),
+        DeltaMode::Wrap => index.rem_euclid(len),
+        DeltaMode::Mirror => {
+            if len == 1 {
+                0
+            } else {
+                let period = 2 * (len - 1);
+                let index = index.rem_euclid(period);
+                if index >= len {
+                    period - index
+                } else {
+                    index
+                }
+            }
+        }
+    };
+    lane[index as usize]
+}
+
+fn dot<F: StftFloat>(weights: &[F], values: ArrayView1<F>) -> F {
+    weights
+        .iter()
+        .zip(values.iter())
+        .fold(F::zero(), |acc, (w, x)| acc + *w * *x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn assert_close(result: &Array2<f64>, expected: &Array2<f64>) {
+        assert_eq!(result.dim(), expected.dim());
+        for (a, e) in result.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+    }
+
+    #[test]
+    fn savgol_coefficients() {
+        // Generated via scipy.signal.savgol_coeffs(5, 2, deriv=..., use='dot')
+        let offsets = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let weights = savgol_weights::<f64>(&offsets, 2, 1);
+        let expected = [-0.2, -0.1, 0.0, 0.1, 0.2];
+        for (a, e) in weights.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-12), "{} ~= {}", a, e);
+        }
+        let weights = savgol_weights::<f64>(&offsets, 2, 2);
+        let expected = [2.0 / 7.0, -1.0 / 7.0, -2.0 / 7.0, -1.0 / 7.0, 2.0 / 7.0];
+        for (a, e) in weights.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-12), "{} ~= {}", a, e);
+        }
+    }
+
+    #[test]
+    fn polynomial_deltas() {
+        // Interpolated edges are exact for polynomials up to the order
+        let ramp = Array2::from_shape_fn((2, 12), |(f, t)| (f + 1) as f64 * t as f64);
+        let delta = ramp.delta(9, 1, Axis(1), DeltaMode::Interp).unwrap();
+        assert_close(&delta, &array![[1.0; 12], [2.0; 12]]);
+
+        let quadratic = Array2::from_shape_fn((12, 1), |(t, _)| (t * t) as f64);
+        let delta = quadratic.delta(5, 2, Axis(0), DeltaMode::Interp).unwrap();
+        assert_close(&delta, &Array2::from_elem((12, 1), 2.0));
+
+        let ramp = Array2::from_shape_fn((1, 5), |(_, t)| t as f64);
+        let delta = ramp.delta(3, 1, Axis(1), DeltaMode::Wrap).unwrap();
+        assert_close(&delta, &array![[-1.5, 1.0, 1.0, 1.0, -1.5]]);
+        let delta = ramp.delta(3, 1, Axis(1), DeltaMode::Mirror).unwrap();
+        assert_close(&delta, &array![[0.0, 1.0, 1.0, 1.0, 0.0]]);
+        let delta = ramp.delta(3, 1, Axis(1), DeltaMode::Constant(0.0)).unwrap();
+        assert_close(&delta, &array![[0.5, 1.0, 1.0, 1.0, -1.5]]);
+    }
+
+    #[test]
+    fn regression_deltas() {
+        // Generated via python_speech_features.delta(feat, 2)
+        let ramp = Array2::from_shape_fn((1, 5), |(_, t)| t as f64);
+        let delta = ramp.regression_delta(2, Axis(1)).unwrap();
+        assert_close(&delta, &array![[0.5, 0.8, 1.0, 0.8, 0.5]]);
+
+        // A first order Savitzky-Golay filter with repeated edges is the same
+        // regression
+        let features = Array2::from_shape_fn((3, 20), |(f, t)| ((f * t) as f64 * 0.3).sin());
+        let savgol = features.delta(5, 1, Axis(1), DeltaMode::Nearest).unwrap();
+        let regression = features.regression_delta(2, Axis(1)).unwrap();
+        assert_close(&savgol, &regression);
+        let transposed = features.t().regression_delta(2, Axis(0)).unwrap();
+        assert_close(&transposed.reversed_axes(), &regression);
+    }
+
+    #[test]
+    fn invalid_delta_parameters() {
+        let features = Array2::<f32>::zeros((13, 8));
+        assert!(features.delta(9, 1, Axis(1), DeltaMode::Interp).is_err());
+        assert!(features.delta(9, 1, Axis(1), DeltaMode::Nearest).is_ok());
+        assert!(features.delta(4, 1, Axis(1), DeltaMode::Nearest).is_err());
+        assert!(features.delta(1, 1, Axis(1), DeltaMode::Nearest).is_err());
+        assert!(features.delta(3, 0, Axis(1), DeltaMode::Nearest).is_err());
+        assert!(features.delta(3, 3, Axis(1), DeltaMode::Nearest).is_err());
+        assert!(features.regression_delta(0, Axis(1)).is_err());
+    }
+}
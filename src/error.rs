@@ -0,0 +1,55 @@
+use std::fmt;
+
+/// Errors returned when audio can't be processed with the given parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The signal doesn't have enough samples to produce any output
+    SignalTooShort {
+        /// Number of samples in the signal
+        samples: usize,
+        /// Minimum number of samples needed
+        required: usize,
+    },
+    /// A hop length of zero was requested
+    ZeroHop,
+    /// The window is longer than the FFT it's centred in
+    WindowLongerThanFft {
+        /// Length of the window
+        win_length: usize,
+        /// Number of points in the FFT
+        n_fft: usize,
+    },
+    /// The input array isn't contiguous in memory
+    NonContiguous,
+    /// A parameter or the shape of an input doesn't match what's expected
+    InvalidParameter(String),
+    /// Audio couldn't be read or written, holding the underlying error message
+    Io(String),
+    /// The reference signal is silent so the metric comparing an estimate to
+    /// it is undefined
+    SilentReference,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SignalTooShort { samples, required } => write!(
+                f,
+                "signal of {} samples is too short, at least {} are required",
+                samples, required
+            ),
+            Self::ZeroHop => write!(f, "hop length must be greater than zero"),
+            Self::WindowLongerThanFft { win_length, n_fft } => write!(
+                f,
+                "window length {} is longer than the FFT size {}",
+                win_length, n_fft
+            ),
+            Self::NonContiguous => write!(f, "input array isn't contiguous in memory"),
+            Self::InvalidParameter(msg) => write!(f, "invalid parameter: {}", msg),
+            Self::Io(msg) => write!(f, "audio I/O failed: {}", msg),
+            Self::SilentReference => write!(f, "reference signal is silent"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
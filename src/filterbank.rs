@@ -0,0 +1,333 @@
+use crate::error::Error;
+use ndarray::{prelude::*, Data, LinalgScalar};
+use num_traits::Float;
+
+/// Scale used to convert between frequencies in Hz and mels
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MelScale {
+    /// The HTK formula `2595 * log10(1 + f/700)`
+    Htk,
+    /// The Slaney formula from the Auditory Toolbox, linear below 1KHz and
+    /// logarithmic above it. This is the librosa default
+    Slaney,
+}
+
+/// Normalisation applied to the triangular filters of the filterbank
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FilterbankNorm {
+    /// Leave the filters with a peak of 1.0
+    None,
+    /// Divide each triangle by the width of its mel band so the filters have
+    /// approximately constant energy per channel
+    Slaney,
+}
+
+const SLANEY_F_SP: f64 = 200.0 / 3.0;
+const SLANEY_MIN_LOG_HZ: f64 = 1000.0;
+const SLANEY_MIN_LOG_MEL: f64 = SLANEY_MIN_LOG_HZ / SLANEY_F_SP;
+
+fn slaney_log_step() -> f64 {
+    6.4f64.ln() / 27.0
+}
+
+impl MelScale {
+    /// Convert a frequency in Hz to mels
+    pub fn hz_to_mel(self, freq: f64) -> f64 {
+        match self {
+            Self::Htk => 2595.0 * (1.0 + freq / 700.0).log10(),
+            Self::Slaney => {
+                if freq >= SLANEY_MIN_LOG_HZ {
+                    SLANEY_MIN_LOG_MEL + (freq / SLANEY_MIN_LOG_HZ).ln() / slaney_log_step()
+                } else {
+                    freq / SLANEY_F_SP
+                }
+            }
+        }
+    }
+
+    /// Convert a value in mels to a frequency in Hz
+    pub fn mel_to_hz(self, mel: f64) -> f64 {
+        match self {
+            Self::Htk => 700.0 * (10.0f64.powf(mel / 2595.0) - 1.0),
+            Self::Slaney => {
+                if mel >= SLANEY_MIN_LOG_MEL {
+                    SLANEY_MIN_LOG_HZ * (slaney_log_step() * (mel - SLANEY_MIN_LOG_MEL)).exp()
+                } else {
+                    SLANEY_F_SP * mel
+                }
+            }
+        }
+    }
+
+    /// Get `n` frequencies in Hz evenly spaced on the mel scale between fmin
+    /// and fmax inclusive
+    pub fn mel_frequencies(self, n: usize, fmin: f64, fmax: f64) -> Array1<f64> {
+        let min_mel = self.hz_to_mel(fmin);
+        let max_mel = self.hz_to_mel(fmax);
+        Array1::linspace(min_mel, max_mel, n).mapv(|m| self.mel_to_hz(m))
+    }
+}
+
+/// Builds a mel filterbank matching `librosa.filters.mel`, setting the
+/// librosa defaults for any unset parameters
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MelFilterbankBuilder {
+    sample_rate: f32,
+    n_fft: usize,
+    n_mels: Option<usize>,
+    fmin: Option<f32>,
+    fmax: Option<f32>,
+    scale: Option<MelScale>,
+    norm: Option<FilterbankNorm>,
+}
+
+impl MelFilterbankBuilder {
+    /// Create a new builder for a signal with the given sample rate that will
+    /// be transformed with an FFT of `n_fft` points
+    pub fn new(sample_rate: f32, n_fft: usize) -> Self {
+        Self {
+            sample_rate,
+            n_fft,
+            n_mels: None,
+            fmin: None,
+            fmax: None,
+            scale: None,
+            norm: None,
+        }
+    }
+
+    /// Set the number of mel bands
+    pub fn set_mel_num(mut self, n: usize) -> Self {
+        self.n_mels = Some(n);
+        self
+    }
+
+    /// Set the lowest frequency in Hz
+    pub fn set_fmin(mut self, fmin: f32) -> Self {
+        self.fmin = Some(fmin);
+        self
+    }
+
+    /// Set the highest frequency in Hz
+    pub fn set_fmax(mut self, fmax: f32) -> Self {
+        self.fmax = Some(fmax);
+        self
+    }
+
+    /// Set the mel scale used to place the filters
+    pub fn set_mel_scale(mut self, scale: MelScale) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Set the normalisation applied to the filters
+    pub fn set_norm(mut self, norm: FilterbankNorm) -> Self {
+        self.norm = Some(norm);
+        self
+    }
+
+    /// Build the MelFilterbank. This uses the following defaults as defined
+    /// by librosa
+    ///
+    /// n_mels default 128
+    /// fmin default 0.0
+    /// fmax default sample_rate/2
+    /// mel_scale default Slaney
+    /// norm default Slaney
+    pub fn build(self) -> MelFilterbank {
+        let n_mels = self.n_mels.unwrap_or(128);
+        let fmin = self.fmin.unwrap_or(0.0) as f64;
+        let fmax = self.fmax.unwrap_or(self.sample_rate / 2.0) as f64;
+        let scale = self.scale.unwrap_or(MelScale::Slaney);
+        let norm = self.norm.unwrap_or(FilterbankNorm::Slaney);
+
+        // Bin centres as given by `numpy.fft.rfftfreq`, for odd FFT sizes the
+        // last bin falls short of the nyquist frequency
+        let n_freqs = 1 + self.n_fft / 2;
+        let fft_freqs = (0..n_freqs)
+            .map(|k| k as f64 * self.sample_rate as f64 / self.n_fft as f64)
+            .collect::<Vec<_>>();
+        let mel_freqs = scale.mel_frequencies(n_mels + 2, fmin, fmax);
+
+        let mut weights = Array2::zeros((n_mels, n_freqs));
+        for (i, mut row) in weights.outer_iter_mut().enumerate() {
+            let lower_width = mel_freqs[i + 1] - mel_freqs[i];
+            let upper_width = mel_freqs[i + 2] - mel_freqs[i + 1];
+            let enorm = match norm {
+                FilterbankNorm::None => 1.0,
+                FilterbankNorm::Slaney => 2.0 / (mel_freqs[i + 2] - mel_freqs[i]),
+            };
+            for (w, f) in row.iter_mut().zip(fft_freqs.iter()) {
+                let lower = (f - mel_freqs[i]) / lower_width;
+                let upper = (mel_freqs[i + 2] - f) / upper_width;
+                *w = (lower.min(upper).max(0.0) * enorm) as f32;
+            }
+        }
+
+        MelFilterbank {
+            sample_rate: self.sample_rate,
+            n_fft: self.n_fft,
+            weights,
+        }
+    }
+}
+
+/// A bank of triangular filters mapping FFT bins onto mel bands. Build using
+/// the MelFilterbankBuilder
+#[derive(Clone, Debug, PartialEq)]
+pub struct MelFilterbank {
+    sample_rate: f32,
+    n_fft: usize,
+    weights: Array2<f32>,
+}
+
+impl MelFilterbank {
+    /// Wrap filter weights created with a different design to the librosa one
+    pub(crate) fn from_weights(sample_rate: f32, n_fft: usize, weights: Array2<f32>) -> Self {
+        Self {
+            sample_rate,
+            n_fft,
+            weights,
+        }
+    }
+
+    /// Sample rate the filterbank was designed for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Number of FFT points the filterbank was designed for
+    pub fn n_fft(&self) -> usize {
+        self.n_fft
+    }
+
+    /// Number of mel bands
+    pub fn n_mels(&self) -> usize {
+        self.weights.nrows()
+    }
+
+    /// The filter weights with shape `(n_mels, 1 + n_fft/2)`
+    pub fn weights(&self) -> ArrayView2<'_, f32> {
+        self.weights.view()
+    }
+
+    /// Apply the filterbank to a spectrogram with shape `(1 + n_fft/2, frames)`
+    /// returning a mel spectrogram of shape `(n_mels, frames)`. Returns an
+    /// error if the number of frequency bins doesn't match the filterbank.
+    /// Double precision spectrograms are supported, with the weights converted
+    /// first
+    pub fn apply<T, F>(&self, spectrogram: &ArrayBase<T, Ix2>) -> Result<Array2<F>, Error>
+    where
+        T: Data<Elem = F>,
+        F: Float + LinalgScalar,
+    {
+        if spectrogram.nrows() != self.weights.ncols() {
+            return Err(Error::InvalidParameter(format!(
+                "spectrogram has {} frequency bins but the filterbank expects {}",
+                spectrogram.nrows(),
+                self.weights.ncols()
+            )));
+        }
+        let weights = self.weights.mapv(|w| F::from(w).unwrap_or_else(F::zero));
+        Ok(weights.dot(spectrogram))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn mel_conversions() {
+        // Values from librosa.hz_to_mel
+        assert!(approx_eq!(
+            f64,
+            MelScale::Slaney.hz_to_mel(440.0),
+            6.6,
+            ulps = 4
+        ));
+        assert!(approx_eq!(
+            f64,
+            MelScale::Slaney.hz_to_mel(1000.0),
+            15.0,
+            ulps = 4
+        ));
+        assert!(approx_eq!(
+            f64,
+            MelScale::Slaney.hz_to_mel(4000.0),
+            35.16376031,
+            epsilon = 1e-7
+        ));
+        assert!(approx_eq!(
+            f64,
+            MelScale::Htk.hz_to_mel(440.0),
+            549.63867538,
+            epsilon = 1e-7
+        ));
+
+        for scale in [MelScale::Htk, MelScale::Slaney].iter() {
+            for freq in [0.0, 100.0, 999.0, 1000.0, 8000.0].iter() {
+                let mel = scale.hz_to_mel(*freq);
+                assert!(approx_eq!(f64, scale.mel_to_hz(mel), *freq, epsilon = 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn filterbank_shape() {
+        let fb = MelFilterbankBuilder::new(16000.0, 512)
+            .set_mel_num(40)
+            .set_norm(FilterbankNorm::None)
+            .build();
+        assert_eq!(fb.weights().dim(), (40, 257));
+        assert_eq!(fb.n_mels(), 40);
+
+        for row in fb.weights().outer_iter() {
+            assert!(row.iter().all(|x| (0.0..=1.0).contains(x)));
+            assert!(row.iter().any(|x| *x > 0.0));
+        }
+
+        let spectrogram = Array2::<f32>::ones((257, 3));
+        let mel = fb.apply(&spectrogram).unwrap();
+        assert_eq!(mel.dim(), (40, 3));
+        assert!(matches!(
+            fb.apply(&Array2::<f32>::ones((256, 3))),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn odd_fft_filterbank() {
+        let fb = MelFilterbankBuilder::new(8000.0, 15).set_mel_num(3).build();
+        // librosa.filters.mel(sr=8000, n_fft=15, n_mels=3), the bins are spaced
+        // by sr / n_fft so the last one is below the nyquist frequency
+        let expected = array![
+            [0.0, 0.00152403, 0.0003512893, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [
+                0.0,
+                0.0,
+                0.0009880686,
+                0.0007385935,
+                6.594998e-5,
+                0.0,
+                0.0,
+                0.0
+            ],
+            [
+                0.0,
+                0.0,
+                0.0,
+                0.0002917476,
+                0.000675221,
+                0.0005238305,
+                0.0003142983,
+                0.0001047661
+            ],
+        ];
+        assert_eq!(fb.weights().dim(), expected.dim());
+        for (a, e) in fb.weights().iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::Error;
+use crate::stft::PadMode;
+use ndarray::{prelude::*, Data, ShapeBuilder};
+use realfft::RealFftPlanner;
+
+/// Split a signal into overlapping frames without copying, as
+/// `librosa.util.frame` does. The output has shape `(frame_length, frames)`
+/// with frame `i` starting at sample `i * hop_length`, and each frame is
+/// contiguous in memory. Trailing samples that don't fill a frame are dropped.
+///
+/// Returns an error if the hop or frame length is zero or the signal is shorter
+/// than a frame
+pub fn frame<T>(
+    signal: &[T],
+    frame_length: usize,
+    hop_length: usize,
+) -> Result<ArrayView2<'_, T>, Error> {
+    if hop_length == 0 {
+        return Err(Error::ZeroHop);
+    }
+    if frame_length == 0 {
+        return Err(Error::InvalidParameter(
+            "frame length must be greater than zero".to_string(),
+        ));
+    }
+    if signal.len() < frame_length {
+        return Err(Error::SignalTooShort {
+            samples: signal.len(),
+            required: frame_length,
+        });
+    }
+    let frames = 1 + (signal.len() - frame_length) / hop_length;
+    let shape = (frame_length, frames).strides((1, hop_length));
+    // ndarray's safe constructors reject overlapping frames even for read-only
+    // views. The last element is at `(frame_length - 1) + (frames - 1) *
+    // hop_length`, which is within the signal by the choice of `frames`, and
+    // the view borrows the signal so it can't outlive it
+    Ok(unsafe { ArrayView2::from_shape_ptr(shape, signal.as_ptr()) })
+}
+
+/// Pad `frame_length / 2` samples either side of the signal with the given
+/// padding mode then split it into frames as `frame` does. This is the framing
+/// of a centred STFT with `n_fft` equal to the frame length, so the frames line
+/// up with the columns of the spectrogram, and the same as librosa's
+/// time-domain features with `center=True`. `PadMode::NoPad` frames the signal
+/// as it is.
+///
+/// Returns an error if the hop or frame length is zero or the padded signal is
+/// shorter than a frame
+pub fn frame_padded<T: Copy + From<f32>>(
+    signal: &[T],
+    frame_length: usize,
+    hop_length: usize,
+    pad_mode: PadMode,
+) -> Result<Array2<T>, Error> {
+    let pad_width = frame_length / 2;
+    let padded = pad_mode.pad(signal, pad_width, pad_width);
+    match frame(&padded, frame_length, hop_length) {
+        Ok(frames) => Ok(frames.to_owned()),
+        Err(Error::SignalTooShort { required, .. }) => Err(Error::SignalTooShort {
+            samples: signal.len(),
+            required: required.saturating_sub(padded.len() - signal.len()),
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Compute the power spectrum `|FFT(frame)|^2` of each column of `frames`,
+/// which has shape `(frame_length, frames)` as returned by `frame`, giving an
+/// array of shape `(1 + n_fft/2, frames)`. Frames shorter than `n_fft` are zero
+/// padded and longer ones are truncated, as `numpy.fft.rfft` does
+pub(crate) fn power_spectrum<S>(frames: &ArrayBase<S, Ix2>, n_fft: usize) -> Array2<f32>
+where
+    S: Data<Elem = f32>,
+{
+    let fft = RealFftPlanner::new().plan_fft_forward(n_fft);
+    let mut buffer = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    let mut scratch = fft.make_scratch_vec();
+    let mut result = Array2::zeros((1 + n_fft / 2, frames.ncols()));
+    for (frame, mut power) in frames.axis_iter(Axis(1)).zip(result.axis_iter_mut(Axis(1))) {
+        buffer.iter_mut().for_each(|x| *x = 0.0);
+        for (b, x) in buffer.iter_mut().zip(frame.iter()) {
+            *b = *x;
+        }
+        fft.process_with_scratch(&mut buffer, &mut spectrum, &mut scratch)
+            .expect("Buffers are sized by the FFT plan");
+        for (p, x) in power.iter_mut().zip(spectrum.iter()) {
+            *p = x.norm_sqr();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stft::StftBuilder;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn strided_frames() {
+        let signal = (0..10).collect::<Vec<i32>>();
+        let frames = frame(&signal, 4, 3).unwrap();
+        // Generated via librosa.util.frame(np.arange(10), frame_length=4, hop_length=3)
+        let expected = array![[0, 3, 6], [1, 4, 7], [2, 5, 8], [3, 6, 9]];
+        assert_eq!(frames, expected);
+        assert_eq!(frames.as_ptr(), signal.as_ptr());
+        for (i, col) in frames.axis_iter(Axis(1)).enumerate() {
+            assert_eq!(col.as_slice().unwrap(), &signal[3 * i..3 * i + 4]);
+        }
+
+        assert_eq!(frame(&signal, 10, 1).unwrap().shape(), &[10, 1]);
+        assert_eq!(frame(&signal, 2, 20).unwrap(), array![[0], [1]]);
+        assert_eq!(frame(&signal, 4, 0), Err(Error::ZeroHop));
+        assert!(frame(&signal, 0, 1).is_err());
+        assert_eq!(
+            frame(&signal, 11, 1),
+            Err(Error::SignalTooShort {
+                samples: 10,
+                required: 11
+            })
+        );
+    }
+
+    #[test]
+    fn padded_frames_match_stft() {
+        let signal = (0..1000).map(|x| x as f32 * 0.01).collect::<Vec<_>>();
+        for (len, hop) in [(64, 16), (65, 10), (256, 128)].iter() {
+            let stft = StftBuilder::new()
+                .set_fft_num(*len)
+                .set_hop_len(*hop)
+                .set_padding_mode(PadMode::Reflect)
+                .build();
+            let frames = frame_padded(&signal, *len, *hop, PadMode::Reflect).unwrap();
+            assert_eq!(frames.ncols(), stft.frame_count(signal.len()));
+            assert_eq!(frames.nrows(), *len);
+            // Each frame is centred on the sample at the start of its hop
+            for (i, col) in frames.axis_iter(Axis(1)).enumerate() {
+                if let Some(x) = signal.get(i * hop) {
+                    assert_eq!(col[*len / 2], *x);
+                }
+            }
+        }
+
+        let frames = frame_padded(&[1.0f32, 2.0, 3.0], 4, 2, PadMode::Zeros).unwrap();
+        assert_eq!(
+            frames,
+            array![[0.0, 1.0], [0.0, 2.0], [1.0, 3.0], [2.0, 0.0]]
+        );
+        let frames = frame_padded(&[1.0f32, 2.0, 3.0], 2, 1, PadMode::NoPad).unwrap();
+        assert_eq!(frames, array![[1.0, 2.0], [2.0, 3.0]]);
+        assert_eq!(
+            frame_padded(&[1.0f32], 4, 1, PadMode::NoPad),
+            Err(Error::SignalTooShort {
+                samples: 1,
+                required: 4
+            })
+        );
+    }
+
+    #[test]
+    fn frame_power_spectrum() {
+        let frames = array![[1.0, 1.0], [1.0, -1.0], [1.0, 1.0], [1.0, -1.0]];
+        let pspec = power_spectrum(&frames, 4);
+        assert_eq!(pspec, array![[16.0, 0.0], [0.0, 0.0], [0.0, 16.0]]);
+
+        // Zero padded to 8 points
+        let pspec = power_spectrum(&frames, 8);
+        assert_eq!(pspec.dim(), (5, 2));
+        assert!(approx_eq!(f32, pspec[[0, 0]], 16.0));
+
+        // Truncated to the first 2 samples of each frame
+        let pspec = power_spectrum(&frames, 2);
+        assert_eq!(pspec, array![[4.0, 0.0], [0.0, 4.0]]);
+    }
+}
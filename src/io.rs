@@ -0,0 +1,393 @@
+use crate::error::Error;
+use crate::sample::{Sample, I24};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use ndarray::{prelude::*, Data};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
+
+/// WAVE_FORMAT_IEEE_FLOAT format tag
+const FORMAT_FLOAT: u16 = 3;
+/// WAVE_FORMAT_EXTENSIBLE format tag, the real format is in the subformat GUID
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Sample format used when writing a WAV file
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WavFormat {
+    /// 8-bit unsigned PCM
+    Int8,
+    /// 16-bit signed PCM
+    #[default]
+    Int16,
+    /// 24-bit signed PCM
+    Int24,
+    /// 32-bit signed PCM
+    Int32,
+    /// 32-bit IEEE float
+    Float32,
+    /// 64-bit IEEE float
+    Float64,
+}
+
+impl WavFormat {
+    /// Number of bits each sample is stored in
+    pub fn bits_per_sample(self) -> u16 {
+        match self {
+            Self::Int8 => 8,
+            Self::Int16 => 16,
+            Self::Int24 => 24,
+            Self::Int32 | Self::Float32 => 32,
+            Self::Float64 => 64,
+        }
+    }
+
+    /// Whether the samples are floating point rather than integer PCM
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::Float32 | Self::Float64)
+    }
+}
+
+/// Read a WAV file from a reader, returning the audio as a `(channels,
+/// samples)` array and the sample rate. 8, 16, 24 and 32-bit PCM is scaled by
+/// `2^(bits - 1)` so full scale is `[-1, 1)`, 32 and 64-bit float samples are
+/// returned as they're stored.
+///
+/// Returns `Error::Io` if the data isn't a WAV file hound can decode
+pub fn read_wav<R: Read>(mut reader: R) -> Result<(Array2<f32>, u32), Error> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes).map_err(io_error)?;
+    // hound only decodes 32-bit float so 64-bit float is parsed here
+    if let Some((channels, sample_rate, data)) = float64_data(&bytes) {
+        let samples = data
+            .chunks_exact(8)
+            .map(|x| {
+                let mut sample = [0; 8];
+                sample.copy_from_slice(x);
+                f64::from_le_bytes(sample) as f32
+            })
+            .collect();
+        return Ok((deinterleave(samples, channels)?, sample_rate));
+    }
+    let mut reader = WavReader::new(Cursor::new(bytes)).map_err(io_error)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_error)?,
+        SampleFormat::Int => {
+            let scale = 2.0f64.powi(i32::from(spec.bits_per_sample) - 1);
+            reader
+                .samples::<i32>()
+                .map(|x| x.map(|x| (f64::from(x) / scale) as f32))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(io_error)?
+        }
+    };
+    Ok((
+        deinterleave(samples, usize::from(spec.channels))?,
+        spec.sample_rate,
+    ))
+}
+
+/// Load a WAV file from disk, see `read_wav`
+pub fn load_wav<P: AsRef<Path>>(path: P) -> Result<(Array2<f32>, u32), Error> {
+    let file = File::open(path).map_err(io_error)?;
+    read_wav(BufReader::new(file))
+}
+
+/// Write `(channels, samples)` audio to a writer as a WAV file. Integer
+/// formats are quantised with `Sample::from_float`, the inverse of `read_wav`,
+/// clipping anything outside of `[-1, 1)` to full scale.
+///
+/// Returns an error if there are no channels or more than `u16::MAX` of them,
+/// or if writing fails
+pub fn write_wav<W, S>(
+    writer: W,
+    audio: &ArrayBase<S, Ix2>,
+    sample_rate: u32,
+    format: WavFormat,
+) -> Result<(), Error>
+where
+    W: Write + Seek,
+    S: Data<Elem = f32>,
+{
+    let channels = match u16::try_from(audio.nrows()) {
+        Ok(0) | Err(_) => {
+            return Err(Error::InvalidParameter(format!(
+                "can't write {} channels to a WAV file",
+                audio.nrows()
+            )))
+        }
+        Ok(x) => x,
+    };
+    let interleaved = audio.t().into_iter().copied();
+    if format == WavFormat::Float64 {
+        return write_float64(writer, channels, sample_rate, interleaved);
+    }
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: format.bits_per_sample(),
+        sample_format: if format.is_float() {
+            SampleFormat::Float
+        } else {
+            SampleFormat::Int
+        },
+    };
+    let mut writer = WavWriter::new(writer, spec).map_err(io_error)?;
+    for x in interleaved {
+        let result = match format {
+            WavFormat::Int8 => writer.write_sample(i8::from_float(x)),
+            WavFormat::Int16 => writer.write_sample(i16::from_float(x)),
+            WavFormat::Int24 => writer.write_sample(I24::from_float(x).to_i32()),
+            WavFormat::Int32 => writer.write_sample(i32::from_float(x)),
+            _ => writer.write_sample(x),
+        };
+        result.map_err(io_error)?;
+    }
+    writer.finalize().map_err(io_error)
+}
+
+/// Save `(channels, samples)` audio to a WAV file on disk, see `write_wav`
+pub fn save_wav<P, S>(
+    path: P,
+    audio: &ArrayBase<S, Ix2>,
+    sample_rate: u32,
+    format: WavFormat,
+) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    S: Data<Elem = f32>,
+{
+    let file = File::create(path).map_err(io_error)?;
+    let mut writer = BufWriter::new(file);
+    write_wav(&mut writer, audio, sample_rate, format)?;
+    writer.flush().map_err(io_error)
+}
+
+fn io_error<E: std::fmt::Display>(e: E) -> Error {
+    Error::Io(e.to_string())
+}
+
+/// Turn interleaved samples into a `(channels, samples)` array, dropping any
+/// trailing partial frame
+fn deinterleave(mut samples: Vec<f32>, channels: usize) -> Result<Array2<f32>, Error> {
+    if channels == 0 {
+        return Err(Error::Io("WAV file has no channels".to_string()));
+    }
+    let frames = samples.len() / channels;
+    samples.truncate(frames * channels);
+    let audio = Array2::from_shape_vec((frames, channels), samples)
+        .map_err(io_error)?
+        .reversed_axes();
+    Ok(audio.as_standard_layout().into_owned())
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+}
+
+/// If the bytes are a 64-bit float WAV returns the channels, sample rate and
+/// the contents of the data chunk
+fn float64_data(bytes: &[u8]) -> Option<(usize, u32, &[u8])> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut format = None;
+    let mut rest = &bytes[12..];
+    while rest.len() >= 8 {
+        let len = u32_at(rest, 4) as usize;
+        let body = &rest[8..];
+        let body = &body[..len.min(body.len())];
+        match &rest[..4] {
+            b"fmt " if body.len() >= 16 => {
+                let tag = u16_at(body, 0);
+                let float = tag == FORMAT_FLOAT
+                    || (tag == FORMAT_EXTENSIBLE
+                        && body.len() >= 26
+                        && u16_at(body, 24) == FORMAT_FLOAT);
+                if !float || u16_at(body, 14) != 64 {
+                    return None;
+                }
+                format = Some((usize::from(u16_at(body, 2)), u32_at(body, 4)));
+            }
+            b"data" => {
+                let (channels, sample_rate) = format?;
+                return Some((channels, sample_rate, body));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even number of bytes
+        let next = 8 + len + (len & 1);
+        if next > rest.len() {
+            break;
+        }
+        rest = &rest[next..];
+    }
+    None
+}
+
+fn write_float64<W, I>(
+    mut writer: W,
+    channels: u16,
+    sample_rate: u32,
+    samples: I,
+) -> Result<(), Error>
+where
+    W: Write,
+    I: ExactSizeIterator<Item = f32>,
+{
+    let too_long = || Error::InvalidParameter("audio is too long for a WAV file".to_string());
+    let frames = u32::try_from(samples.len() / usize::from(channels)).map_err(|_| too_long())?;
+    let block_align = 8 * channels;
+    let data_len = frames
+        .checked_mul(u32::from(block_align))
+        .ok_or_else(too_long)?;
+    // WAVE id, fmt chunk, fact chunk and data chunk header
+    let riff_len = data_len.checked_add(4 + 24 + 12 + 8).ok_or_else(too_long)?;
+    let byte_rate = sample_rate.saturating_mul(u32::from(block_align));
+
+    let mut header = Vec::with_capacity(56);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&riff_len.to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&FORMAT_FLOAT.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&byte_rate.to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&64u16.to_le_bytes());
+    // Non-PCM formats should have a fact chunk with the number of frames
+    header.extend_from_slice(b"fact");
+    header.extend_from_slice(&4u32.to_le_bytes());
+    header.extend_from_slice(&frames.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    writer.write_all(&header).map_err(io_error)?;
+    for x in samples {
+        writer
+            .write_all(&f64::from(x).to_le_bytes())
+            .map_err(io_error)?;
+    }
+    writer.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn round_trip(audio: &Array2<f32>, format: WavFormat) -> (Array2<f32>, u32) {
+        let mut buffer = Cursor::new(vec![]);
+        write_wav(&mut buffer, audio, 22050, format).unwrap();
+        read_wav(Cursor::new(buffer.into_inner())).unwrap()
+    }
+
+    #[test]
+    fn pcm_normalisation() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer = WavWriter::new(&mut buffer, spec).unwrap();
+        for x in &[i16::MIN, 0, 16384, i16::MAX, -16384, 1] {
+            writer.write_sample(*x).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (audio, sample_rate) = read_wav(Cursor::new(buffer.into_inner())).unwrap();
+        assert_eq!(sample_rate, 16000);
+        let expected = array![[-1.0, 0.5, -0.5], [0.0, 32767.0 / 32768.0, 1.0 / 32768.0]];
+        assert_eq!(audio, expected);
+        assert!(audio.is_standard_layout());
+
+        let spec = WavSpec {
+            channels: 1,
+            bits_per_sample: 8,
+            ..spec
+        };
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer = WavWriter::new(&mut buffer, spec).unwrap();
+        for x in &[i8::MIN, 0, 64, i8::MAX] {
+            writer.write_sample(*x).unwrap();
+        }
+        writer.finalize().unwrap();
+        let (audio, _) = read_wav(Cursor::new(buffer.into_inner())).unwrap();
+        assert_eq!(audio, array![[-1.0, 0.0, 0.5, 127.0 / 128.0]]);
+    }
+
+    #[test]
+    fn write_and_read_formats() {
+        let audio = array![
+            [0.0f32, 0.25, -0.5, 0.75, -1.0, 0.125],
+            [0.5, -0.25, 0.0, -0.75, 0.375, -0.125]
+        ];
+        let formats = [
+            WavFormat::Int8,
+            WavFormat::Int16,
+            WavFormat::Int24,
+            WavFormat::Int32,
+            WavFormat::Float32,
+            WavFormat::Float64,
+        ];
+        for format in formats.iter() {
+            let (decoded, sample_rate) = round_trip(&audio, *format);
+            assert_eq!(sample_rate, 22050);
+            assert_eq!(decoded, audio, "{:?}", format);
+        }
+
+        let noise = Array2::from_shape_fn((3, 100), |(c, i)| ((c * 100 + i) as f32 * 0.37).sin());
+        for format in formats.iter() {
+            let (decoded, _) = round_trip(&noise, *format);
+            let tol = 2.0f32.powi(1 - i32::from(format.bits_per_sample().min(24)));
+            for (a, e) in decoded.iter().zip(noise.iter()) {
+                assert!(approx_eq!(f32, *a, *e, epsilon = tol), "{} ~= {}", a, e);
+            }
+        }
+    }
+
+    #[test]
+    fn clipping() {
+        let audio = array![[2.0f32, -2.0, 1.0, f32::NAN]];
+        let (decoded, _) = round_trip(&audio, WavFormat::Int16);
+        assert_eq!(
+            decoded,
+            array![[32767.0 / 32768.0, -1.0, 32767.0 / 32768.0, 0.0]]
+        );
+
+        let (decoded, _) = round_trip(&audio.slice(s![.., ..3]).to_owned(), WavFormat::Float64);
+        assert_eq!(decoded, array![[2.0, -2.0, 1.0]]);
+    }
+
+    #[test]
+    fn invalid_wavs() {
+        let audio = Array2::<f32>::zeros((0, 10));
+        assert!(write_wav(Cursor::new(vec![]), &audio, 16000, WavFormat::Int16).is_err());
+        assert!(matches!(
+            read_wav(&b"RIFF\0\0\0\0WAVE"[..]),
+            Err(Error::Io(_))
+        ));
+        assert!(matches!(read_wav(&[][..]), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn load_and_save() {
+        let audio = array![[0.5f32, -0.5, 0.25]];
+        let path = std::env::temp_dir().join(format!("emo_audio_io_{}.wav", std::process::id()));
+        save_wav(&path, &audio, 8000, WavFormat::Float32).unwrap();
+        let loaded = load_wav(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), (audio, 8000));
+    }
+}
@@ -0,0 +1,646 @@
+use crate::error::Error;
+use crate::filterbank::{MelFilterbank, MelScale};
+use crate::frame::{frame, power_spectrum};
+use crate::mfcc::{dct_matrix, lifter_weights};
+use crate::window::{WindowMode, WindowingAlgorithm};
+use ndarray::prelude::*;
+use num_traits::{Bounded, Num, NumCast};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Builds a KaldiFeatures instance. Any parameters not set take the defaults
+/// of Kaldi's `compute-fbank-feats` and `compute-mfcc-feats`, which are also
+/// the defaults of `torchaudio.compliance.kaldi`
+#[derive(Clone, Debug, PartialEq)]
+pub struct KaldiFeaturesBuilder {
+    sample_rate: f32,
+    frame_length: Option<f32>,
+    frame_shift: Option<f32>,
+    snip_edges: Option<bool>,
+    dither: Option<f32>,
+    remove_dc_offset: Option<bool>,
+    window: Option<WindowingAlgorithm>,
+    preemphasis: Option<f32>,
+    round_to_power_of_two: Option<bool>,
+    raw_energy: Option<bool>,
+    energy_floor: Option<f32>,
+    use_energy: Option<bool>,
+    n_mels: Option<usize>,
+    low_freq: Option<f32>,
+    high_freq: Option<f32>,
+    n_ceps: Option<usize>,
+    cepstral_lifter: Option<f32>,
+}
+
+impl KaldiFeaturesBuilder {
+    /// Create a builder for a signal with the given sample rate
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            frame_length: None,
+            frame_shift: None,
+            snip_edges: None,
+            dither: None,
+            remove_dc_offset: None,
+            window: None,
+            preemphasis: None,
+            round_to_power_of_two: None,
+            raw_energy: None,
+            energy_floor: None,
+            use_energy: None,
+            n_mels: None,
+            low_freq: None,
+            high_freq: None,
+            n_ceps: None,
+            cepstral_lifter: None,
+        }
+    }
+
+    /// Set the length of each frame in milliseconds
+    pub fn set_frame_length(mut self, ms: f32) -> Self {
+        self.frame_length = Some(ms);
+        self
+    }
+
+    /// Set the time between the starts of successive frames in milliseconds
+    pub fn set_frame_shift(mut self, ms: f32) -> Self {
+        self.frame_shift = Some(ms);
+        self
+    }
+
+    /// Sets whether only frames that fit entirely in the signal are kept. If
+    /// false the number of frames depends only on the frame shift and the
+    /// signal is reflected at the edges to fill the frames
+    pub fn set_snip_edges(mut self, snip_edges: bool) -> Self {
+        self.snip_edges = Some(snip_edges);
+        self
+    }
+
+    /// Set the standard deviation of the gaussian noise added to every sample,
+    /// 0 disables dithering. Kaldi uses 1.0 for 16 bit integer samples
+    pub fn set_dither(mut self, dither: f32) -> Self {
+        self.dither = Some(dither);
+        self
+    }
+
+    /// Sets whether the mean of each frame is subtracted from it
+    pub fn set_remove_dc_offset(mut self, remove_dc_offset: bool) -> Self {
+        self.remove_dc_offset = Some(remove_dc_offset);
+        self
+    }
+
+    /// Set the window applied to each frame. Kaldi windows are symmetric
+    pub fn set_window(mut self, window: WindowingAlgorithm) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Set the preemphasis coefficient, 0 disables preemphasis
+    pub fn set_preemphasis(mut self, coefficient: f32) -> Self {
+        self.preemphasis = Some(coefficient);
+        self
+    }
+
+    /// Sets whether frames are zero padded to the next power of two before
+    /// the FFT
+    pub fn set_round_to_power_of_two(mut self, round: bool) -> Self {
+        self.round_to_power_of_two = Some(round);
+        self
+    }
+
+    /// Sets whether the frame energy is taken before preemphasis and
+    /// windowing rather than after
+    pub fn set_raw_energy(mut self, raw_energy: bool) -> Self {
+        self.raw_energy = Some(raw_energy);
+        self
+    }
+
+    /// Set the floor of the frame energy, the log energy is clipped below at
+    /// the log of this value. 0 disables the floor
+    pub fn set_energy_floor(mut self, floor: f32) -> Self {
+        self.energy_floor = Some(floor);
+        self
+    }
+
+    /// Sets whether the log frame energy is included in the features. For
+    /// filterbanks it's prepended as an extra row and for MFCCs it replaces
+    /// the zeroth coefficient
+    pub fn set_use_energy(mut self, use_energy: bool) -> Self {
+        self.use_energy = Some(use_energy);
+        self
+    }
+
+    /// Set the number of mel filters
+    pub fn set_mel_num(mut self, n: usize) -> Self {
+        self.n_mels = Some(n);
+        self
+    }
+
+    /// Set the lowest frequency of the mel filters in Hz
+    pub fn set_low_freq(mut self, freq: f32) -> Self {
+        self.low_freq = Some(freq);
+        self
+    }
+
+    /// Set the highest frequency of the mel filters in Hz. Values of zero or
+    /// less are taken relative to the nyquist frequency
+    pub fn set_high_freq(mut self, freq: f32) -> Self {
+        self.high_freq = Some(freq);
+        self
+    }
+
+    /// Set the number of cepstral coefficients to return
+    pub fn set_ceps_num(mut self, n: usize) -> Self {
+        self.n_ceps = Some(n);
+        self
+    }
+
+    /// Set the cepstral lifter coefficient, 0 disables liftering
+    pub fn set_cepstral_lifter(mut self, lifter: f32) -> Self {
+        self.cepstral_lifter = Some(lifter);
+        self
+    }
+
+    /// Build the KaldiFeatures instance. The defaults are
+    ///
+    /// frame_length default 25ms
+    /// frame_shift default 10ms
+    /// snip_edges default true
+    /// dither default 0
+    /// remove_dc_offset default true
+    /// window default Povey
+    /// preemphasis default 0.97
+    /// round_to_power_of_two default true
+    /// raw_energy default true
+    /// energy_floor default 1.0
+    /// use_energy default false
+    /// n_mels default 23
+    /// low_freq default 20Hz
+    /// high_freq default 0, the nyquist frequency
+    /// n_ceps default 13
+    /// cepstral_lifter default 22
+    ///
+    /// Kaldi's own dither default is 1.0 but torchaudio disables it
+    ///
+    /// # Panics
+    ///
+    /// If the parameters are invalid, see `try_build` for a fallible version
+    pub fn build(self) -> KaldiFeatures {
+        match self.try_build() {
+            Ok(features) => features,
+            Err(e) => panic!("Invalid Kaldi feature parameters: {}", e),
+        }
+    }
+
+    /// Build the KaldiFeatures instance using the same defaults as `build`,
+    /// returning an error if the frames are shorter than two samples or have
+    /// no shift, the mel filters don't lie between 0Hz and the nyquist
+    /// frequency or there are more cepstral coefficients than mel filters
+    pub fn try_build(self) -> Result<KaldiFeatures, Error> {
+        // Kaldi truncates the frame sizes to whole samples
+        let sample_rate = self.sample_rate as f64;
+        let frame_shift = (sample_rate * self.frame_shift.unwrap_or(10.0) as f64 * 0.001) as usize;
+        let frame_length =
+            (sample_rate * self.frame_length.unwrap_or(25.0) as f64 * 0.001) as usize;
+        if frame_shift == 0 {
+            return Err(Error::ZeroHop);
+        }
+        if frame_length < 2 {
+            return Err(Error::InvalidParameter(
+                "frame length must be at least 2 samples".to_string(),
+            ));
+        }
+        let n_fft = if self.round_to_power_of_two.unwrap_or(true) {
+            frame_length.next_power_of_two()
+        } else {
+            frame_length
+        };
+
+        let n_mels = self.n_mels.unwrap_or(23);
+        let n_ceps = self.n_ceps.unwrap_or(13);
+        if n_ceps > n_mels {
+            return Err(Error::InvalidParameter(format!(
+                "{} cepstral coefficients requested from {} mel filters",
+                n_ceps, n_mels
+            )));
+        }
+        let weights = mel_banks(
+            n_mels,
+            n_fft,
+            sample_rate,
+            self.low_freq.unwrap_or(20.0) as f64,
+            self.high_freq.unwrap_or(0.0) as f64,
+        )?;
+
+        let window = self
+            .window
+            .unwrap_or(WindowingAlgorithm::Povey)
+            .generate(frame_length, WindowMode::Symmetric);
+        Ok(KaldiFeatures {
+            frame_length,
+            frame_shift,
+            snip_edges: self.snip_edges.unwrap_or(true),
+            dither: self.dither.unwrap_or(0.0),
+            remove_dc_offset: self.remove_dc_offset.unwrap_or(true),
+            window,
+            preemphasis: self.preemphasis.unwrap_or(0.97),
+            raw_energy: self.raw_energy.unwrap_or(true),
+            energy_floor: self.energy_floor.unwrap_or(1.0),
+            use_energy: self.use_energy.unwrap_or(false),
+            filterbank: MelFilterbank::from_weights(self.sample_rate, n_fft, weights),
+            dct: dct_matrix(n_ceps, n_mels),
+            lifter: lifter_weights(n_ceps, self.cepstral_lifter.unwrap_or(22.0), 0),
+        })
+    }
+}
+
+/// Computes filterbank and MFCC features following the conventions of Kaldi
+/// and `torchaudio.compliance.kaldi`. Build using the KaldiFeaturesBuilder
+#[derive(Clone, Debug, PartialEq)]
+pub struct KaldiFeatures {
+    frame_length: usize,
+    frame_shift: usize,
+    snip_edges: bool,
+    dither: f32,
+    remove_dc_offset: bool,
+    window: Array1<f32>,
+    preemphasis: f32,
+    raw_energy: bool,
+    energy_floor: f32,
+    use_energy: bool,
+    filterbank: MelFilterbank,
+    dct: Array2<f32>,
+    lifter: Array1<f32>,
+}
+
+impl KaldiFeatures {
+    /// The length of each frame in samples
+    pub fn frame_length(&self) -> usize {
+        self.frame_length
+    }
+
+    /// The number of samples between the starts of successive frames
+    pub fn frame_shift(&self) -> usize {
+        self.frame_shift
+    }
+
+    /// The number of frames produced for a signal of `samples` length
+    pub fn frame_count(&self, samples: usize) -> usize {
+        if self.snip_edges {
+            if samples < self.frame_length {
+                0
+            } else {
+                1 + (samples - self.frame_length) / self.frame_shift
+            }
+        } else {
+            (samples + self.frame_shift / 2) / self.frame_shift
+        }
+    }
+
+    /// The mel filterbank with shape `(n_mels, 1 + n_fft/2)`. Kaldi filters
+    /// never include the nyquist bin
+    pub fn filterbank(&self) -> &MelFilterbank {
+        &self.filterbank
+    }
+
+    /// Compute the log mel filterbank energies of the signal as
+    /// `torchaudio.compliance.kaldi.fbank` does, returning an array of shape
+    /// `(n_mels, frames)`. If `use_energy` is set the log frame energy is the
+    /// first row, giving `n_mels + 1` rows. Returns an error if the signal is
+    /// too short for a single frame
+    pub fn fbank<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Result<Array2<f32>, Error> {
+        let (mel, energy) = self.log_mel_energies(samples)?;
+        if self.use_energy {
+            let mut result = Array2::zeros((mel.nrows() + 1, mel.ncols()));
+            result.row_mut(0).assign(&energy);
+            result.slice_mut(s![1.., ..]).assign(&mel);
+            Ok(result)
+        } else {
+            Ok(mel)
+        }
+    }
+
+    /// Compute the MFCCs of the signal as `torchaudio.compliance.kaldi.mfcc`
+    /// does, returning an array of shape `(n_ceps, frames)`. If `use_energy`
+    /// is set the zeroth coefficient is replaced by the log frame energy.
+    /// Returns an error if the signal is too short for a single frame
+    pub fn mfcc<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Result<Array2<f32>, Error> {
+        let (mel, energy) = self.log_mel_energies(samples)?;
+        let mut cepstra = self.dct.dot(&mel);
+        cepstra *= &self.lifter.view().insert_axis(Axis(1));
+        if self.use_energy {
+            cepstra.row_mut(0).assign(&energy);
+        }
+        Ok(cepstra)
+    }
+
+    /// Compute the log mel energies with shape `(n_mels, frames)` and the log
+    /// energy of each frame
+    fn log_mel_energies<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+    ) -> Result<(Array2<f32>, Array1<f32>), Error> {
+        let count = self.frame_count(samples.len());
+        if count == 0 {
+            let required = if self.snip_edges {
+                self.frame_length
+            } else {
+                self.frame_shift - self.frame_shift / 2
+            };
+            return Err(Error::SignalTooShort {
+                samples: samples.len(),
+                required,
+            });
+        }
+        let signal = self.extend_signal(samples);
+        // The frames are copied into a column major array so each can be
+        // processed in place before it's windowed
+        let mut frames = Array2::zeros((self.frame_length, count).f());
+        frames.assign(&frame(&signal, self.frame_length, self.frame_shift)?.slice(s![.., ..count]));
+        let mut energy = Array1::zeros(count);
+        let mut rng = rand::thread_rng();
+
+        for (mut frame, energy) in frames.axis_iter_mut(Axis(1)).zip(energy.iter_mut()) {
+            let frame = frame
+                .as_slice_mut()
+                .expect("Columns of a column major array are contiguous");
+            if self.dither != 0.0 {
+                for x in frame.iter_mut() {
+                    *x += self.dither * gaussian(&mut rng);
+                }
+            }
+            if self.remove_dc_offset {
+                let mean = frame.iter().sum::<f32>() / frame.len() as f32;
+                frame.iter_mut().for_each(|x| *x -= mean);
+            }
+            if self.raw_energy {
+                *energy = self.log_energy(frame);
+            }
+            if self.preemphasis != 0.0 {
+                for j in (1..frame.len()).rev() {
+                    frame[j] -= self.preemphasis * frame[j - 1];
+                }
+                frame[0] -= self.preemphasis * frame[0];
+            }
+            for (x, w) in frame.iter_mut().zip(self.window.iter()) {
+                *x *= w;
+            }
+            if !self.raw_energy {
+                *energy = self.log_energy(frame);
+            }
+        }
+
+        let power = power_spectrum(&frames, self.filterbank.n_fft());
+        let mel = self
+            .filterbank
+            .apply(&power)?
+            .mapv(|x| x.max(f32::EPSILON).ln());
+        Ok((mel, energy))
+    }
+
+    /// Get the samples the frames are taken from. If the edges are snipped
+    /// this is the signal, otherwise the start is reflected to centre the
+    /// first frame on the first sample and the reversed signal is appended
+    fn extend_signal<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Vec<f32> {
+        let signal = samples.iter().map(|x| x.to_f32().unwrap_or_default());
+        if self.snip_edges {
+            return signal.collect();
+        }
+        let signal = signal.collect::<Vec<_>>();
+        let count = self.frame_count(signal.len());
+        if count == 0 {
+            return Vec::new();
+        }
+        let pad = (self.frame_length / 2) as isize - (self.frame_shift / 2) as isize;
+        let length = (count - 1) * self.frame_shift + self.frame_length;
+        (0..length as isize)
+            .map(|i| signal[reflect(i - pad, signal.len())])
+            .collect()
+    }
+
+    /// Log of the frame energy, floored at the energy floor and epsilon
+    fn log_energy(&self, frame: &[f32]) -> f32 {
+        let energy = frame
+            .iter()
+            .map(|x| x * x)
+            .sum::<f32>()
+            .max(f32::EPSILON)
+            .ln();
+        if self.energy_floor == 0.0 {
+            energy
+        } else {
+            energy.max(self.energy_floor.ln())
+        }
+    }
+}
+
+/// Create Kaldi's mel filterbank with shape `(n_mels, 1 + n_fft/2)`. The
+/// filters are triangular on the mel scale rather than in Hz, and the nyquist
+/// bin is always zero
+fn mel_banks(
+    n_mels: usize,
+    n_fft: usize,
+    sample_rate: f64,
+    low_freq: f64,
+    high_freq: f64,
+) -> Result<Array2<f32>, Error> {
+    let nyquist = 0.5 * sample_rate;
+    let high_freq = if high_freq <= 0.0 {
+        high_freq + nyquist
+    } else {
+        high_freq
+    };
+    if low_freq < 0.0 || low_freq >= nyquist || high_freq <= 0.0 || high_freq > nyquist {
+        return Err(Error::InvalidParameter(format!(
+            "mel filters between {}Hz and {}Hz don't lie between 0Hz and the nyquist frequency {}Hz",
+            low_freq, high_freq, nyquist
+        )));
+    }
+    if low_freq >= high_freq {
+        return Err(Error::InvalidParameter(format!(
+            "low frequency {}Hz isn't below the high frequency {}Hz",
+            low_freq, high_freq
+        )));
+    }
+
+    let bin_width = sample_rate / n_fft as f64;
+    let mel_low = MelScale::Htk.hz_to_mel(low_freq);
+    let mel_high = MelScale::Htk.hz_to_mel(high_freq);
+    let mel_delta = (mel_high - mel_low) / (n_mels + 1) as f64;
+    let mut weights = Array2::zeros((n_mels, 1 + n_fft / 2));
+    for (m, mut filter) in weights.outer_iter_mut().enumerate() {
+        let left = mel_low + m as f64 * mel_delta;
+        let centre = left + mel_delta;
+        let right = centre + mel_delta;
+        for (i, w) in filter.iter_mut().take(n_fft / 2).enumerate() {
+            let mel = MelScale::Htk.hz_to_mel(bin_width * i as f64);
+            let up = (mel - left) / (centre - left);
+            let down = (right - mel) / (right - centre);
+            *w = up.min(down).max(0.0) as f32;
+        }
+    }
+    Ok(weights)
+}
+
+/// Sample from a standard normal distribution using the Box-Muller transform
+fn gaussian<R: Rng>(rng: &mut R) -> f32 {
+    // Shift the first sample to (0, 1] so the logarithm is finite
+    let u1 = 1.0 - rng.gen::<f32>();
+    let u2 = rng.gen::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// Map an index outside `0..len` back into the signal by reflecting it about
+/// the edges, repeating the first and last samples, as Kaldi's `ExtractWindow`
+/// does. The reflection is repeated for signals shorter than the padding
+fn reflect(mut index: isize, len: usize) -> usize {
+    let len = len as isize;
+    while index < 0 || index >= len {
+        index = if index < 0 {
+            -index - 1
+        } else {
+            2 * len - 1 - index
+        };
+    }
+    index as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn kaldi_framing() {
+        let features = KaldiFeaturesBuilder::new(16000.0).build();
+        assert_eq!(features.frame_length(), 400);
+        assert_eq!(features.frame_shift(), 160);
+        assert_eq!(features.filterbank().n_fft(), 512);
+        assert_eq!(features.frame_count(16000), 98);
+        assert_eq!(features.frame_count(399), 0);
+
+        let features = KaldiFeaturesBuilder::new(1000.0)
+            .set_frame_length(4.0)
+            .set_frame_shift(2.0)
+            .set_snip_edges(false)
+            .build();
+        assert_eq!(features.frame_count(16000), 8000);
+        assert_eq!(features.frame_count(5), 3);
+        // The first sample is repeated when the start is reflected
+        let signal = features.extend_signal(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(signal, vec![1.0, 1.0, 2.0, 3.0, 4.0, 5.0, 5.0, 4.0]);
+        // Signals shorter than the padding are reflected repeatedly
+        let signal = features.extend_signal(&[1.0, 2.0]);
+        assert_eq!(signal, vec![1.0, 1.0, 2.0, 2.0]);
+
+        let features = KaldiFeaturesBuilder::new(1000.0)
+            .set_frame_length(4.0)
+            .set_frame_shift(6.0)
+            .set_snip_edges(false)
+            .build();
+        let signal = features.extend_signal(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(signal, vec![2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn kaldi_short_signal() {
+        let features = KaldiFeaturesBuilder::new(16000.0)
+            .set_snip_edges(false)
+            .build();
+        for samples in [300, 100, 80] {
+            let fbank = features.fbank(&vec![0.5f32; samples]).unwrap();
+            assert_eq!(fbank.ncols(), features.frame_count(samples));
+            assert!(fbank.iter().all(|x| x.is_finite()));
+        }
+        assert_eq!(features.frame_count(300), 2);
+        assert_eq!(
+            features.fbank(&[0.5f32; 79]),
+            Err(Error::SignalTooShort {
+                samples: 79,
+                required: 80
+            })
+        );
+    }
+
+    #[test]
+    fn kaldi_mel_banks() {
+        let weights = mel_banks(23, 512, 16000.0, 20.0, 0.0).unwrap();
+        assert_eq!(weights.dim(), (23, 257));
+        assert!(weights.column(256).iter().all(|x| *x == 0.0));
+        assert!(weights.iter().all(|x| *x >= 0.0 && *x <= 1.0));
+        // Consecutive filters cross where the first is falling and the next
+        // is rising, so away from the edges the weights sum to about one
+        let total = weights.sum_axis(Axis(0));
+        for x in total.slice(s![20..200]).iter() {
+            assert!(approx_eq!(f32, *x, 1.0, epsilon = 1e-5), "{}", x);
+        }
+
+        assert!(mel_banks(23, 512, 16000.0, 20.0, 9000.0).is_err());
+        assert!(mel_banks(23, 512, 16000.0, 4000.0, -4000.0).is_err());
+        assert!(KaldiFeaturesBuilder::new(16000.0)
+            .set_ceps_num(30)
+            .try_build()
+            .is_err());
+        assert_eq!(
+            KaldiFeaturesBuilder::new(16000.0)
+                .set_frame_shift(0.01)
+                .try_build(),
+            Err(Error::ZeroHop)
+        );
+    }
+
+    #[test]
+    fn kaldi_features() {
+        let signal = (0..16000)
+            .map(|x| (x as f32 * 0.05).sin() * 1000.0)
+            .collect::<Vec<_>>();
+        let features = KaldiFeaturesBuilder::new(16000.0)
+            .set_use_energy(true)
+            .build();
+        let fbank = features.fbank(&signal).unwrap();
+        let mfcc = features.mfcc(&signal).unwrap();
+        assert_eq!(fbank.dim(), (24, 98));
+        assert_eq!(mfcc.dim(), (13, 98));
+        assert_eq!(fbank.row(0), mfcc.row(0));
+
+        // Without the energy the zeroth coefficient is the scaled mean of the
+        // log mel energies
+        let features = KaldiFeaturesBuilder::new(16000.0).build();
+        let fbank = features.fbank(&signal).unwrap();
+        let mfcc = features.mfcc(&signal).unwrap();
+        assert_eq!(fbank.dim(), (23, 98));
+        for (c0, frame) in mfcc.row(0).iter().zip(fbank.columns()) {
+            let expected = frame.sum() / 23.0f32.sqrt();
+            assert!(approx_eq!(f32, *c0, expected, epsilon = 1e-3));
+        }
+
+        // A constant signal is removed by the DC offset so the energy is
+        // floored and the mel energies clipped at epsilon
+        let features = KaldiFeaturesBuilder::new(16000.0)
+            .set_use_energy(true)
+            .build();
+        let fbank = features.fbank(&[100i16; 1000]).unwrap();
+        assert!(fbank.row(0).iter().all(|x| *x == 0.0));
+        assert!(fbank
+            .slice(s![1.., ..])
+            .iter()
+            .all(|x| *x == f32::EPSILON.ln()));
+
+        let dithered = KaldiFeaturesBuilder::new(16000.0)
+            .set_dither(1.0)
+            .set_use_energy(true)
+            .build()
+            .fbank(&[100i16; 1000])
+            .unwrap();
+        assert!(dithered.iter().all(|x| x.is_finite()));
+        assert!(dithered.row(0).iter().all(|x| *x > 0.0));
+
+        assert_eq!(
+            features.mfcc(&[0.0f32; 10]),
+            Err(Error::SignalTooShort {
+                samples: 10,
+                required: 400
+            })
+        );
+    }
+}
@@ -1,19 +1,59 @@
 //! Emo-audio is designed to contain a set of audio processing routines built
 //! around ndarray.
 
+pub use error::Error;
+
+/// Conversions between power or amplitude spectrograms and decibels
+pub mod decibel;
+/// Delta features reproducing librosa and python_speech_features
+pub mod deltas;
+/// Error type for failures processing audio
+pub mod error;
+/// Mel filterbank construction and frequency scale conversions
+pub mod filterbank;
+/// Splitting signals into overlapping frames
+pub mod frame;
+/// Reading and writing WAV files, enabled by the `io` feature
+#[cfg(feature = "io")]
+pub mod io;
+/// Filterbank and MFCC features following the conventions of Kaldi
+pub mod kaldi;
 /// Metrics that can be gathered from the time or frequency domains
 pub mod metrics;
+/// Mel frequency cepstral coefficients reproducing librosa and
+/// python_speech_features
+pub mod mfcc;
 /// Preemphasis extension trait for 1D arrays
 pub mod preemphasis;
+/// Sample rate conversion by band-limited sinc interpolation
+pub mod resample;
+/// Conversions between integer PCM and floating point samples
+pub mod sample;
 /// Takes an audio signal and returns a spectrogram
 pub mod spectrum;
+/// Filterbank features reproducing python_speech_features
+pub mod speech_features;
 /// Module containing an implementation of a short time fourier transform
 pub mod stft;
+/// Window functions mirroring those provided by scipy
+pub mod window;
 
 /// Common imports
 pub mod prelude {
+    pub use crate::decibel::*;
+    pub use crate::deltas::*;
+    pub use crate::filterbank::*;
+    pub use crate::frame::*;
+    #[cfg(feature = "io")]
+    pub use crate::io::*;
+    pub use crate::kaldi::*;
     pub use crate::metrics::*;
+    pub use crate::mfcc::*;
     pub use crate::preemphasis::*;
+    pub use crate::resample::*;
+    pub use crate::sample::*;
     pub use crate::spectrum::*;
+    pub use crate::speech_features::*;
     pub use crate::stft::*;
+    pub use crate::window::*;
 }
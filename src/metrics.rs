@@ -1,4 +1,13 @@
-use num_traits::{Num, NumCast};
+use crate::error::Error;
+use crate::frame::{frame, frame_padded};
+use crate::stft::PadMode;
+use ndarray::{prelude::*, AsArray, Data};
+use num_traits::{Float, Num, NumCast};
+use realfft::RealFftPlanner;
+use rustfft::num_complex::Complex;
+
+/// Per segment SNRs are clamped to this range in decibels by `segmental_snr`
+const SEGMENT_SNR_RANGE: (f64, f64) = (-10.0, 35.0);
 
 /// Calculate the power of a signal
 pub fn power<T: Num + NumCast>(samples: &[T]) -> f64 {
@@ -13,9 +22,415 @@ pub fn rms<T: Num + NumCast>(samples: &[T]) -> f64 {
     power(samples).sqrt()
 }
 
+/// Calculate the root mean square of every frame of the signal as
+/// `librosa.feature.rms` does, padding by `frame_length / 2` either side unless
+/// the mode is `PadMode::NoPad`. Returns an error if the hop or frame length is
+/// zero or the signal is shorter than a frame
+pub fn frame_rms<T: Num + NumCast>(
+    samples: &[T],
+    frame_length: usize,
+    hop_length: usize,
+    pad_mode: PadMode,
+) -> Result<Array1<f64>, Error> {
+    let energy = frame_energy(samples, frame_length, hop_length, pad_mode)?;
+    Ok(energy.mapv(|x| (x / frame_length as f64).sqrt()))
+}
+
+/// Calculate the sum of the squared samples of every frame of the signal,
+/// framed as `frame_rms` does. Returns an error if the hop or frame length is
+/// zero or the signal is shorter than a frame
+pub fn frame_energy<T: Num + NumCast>(
+    samples: &[T],
+    frame_length: usize,
+    hop_length: usize,
+    pad_mode: PadMode,
+) -> Result<Array1<f64>, Error> {
+    let frames = frame_padded(&to_f64(samples), frame_length, hop_length, pad_mode)?;
+    Ok(frames.map_axis(Axis(0), |x| x.fold(0.0, |acc, x| acc + x * x)))
+}
+
+/// Calculate the zero crossing rate of every frame of the signal, framed as
+/// `frame_rms` does, as `librosa.feature.zero_crossing_rate` does. Returns an
+/// error if the hop or frame length is zero or the signal is shorter than a frame
+pub fn zero_crossing_rate<T: Num + NumCast>(
+    samples: &[T],
+    frame_length: usize,
+    hop_length: usize,
+    pad_mode: PadMode,
+) -> Result<Array1<f64>, Error> {
+    let frames = frame_padded(&to_f64(samples), frame_length, hop_length, pad_mode)?;
+    let negative = |x: f64| x.abs() > 1e-10 && x.is_sign_negative();
+    Ok(frames.map_axis(Axis(0), |frame| {
+        let crossings = frame
+            .iter()
+            .zip(frame.iter().skip(1))
+            .filter(|(a, b)| negative(**a) != negative(**b))
+            .count();
+        crossings as f64 / frame_length as f64
+    }))
+}
+
+/// Calculate the root mean square of every frame from a magnitude spectrogram
+/// of shape `(1 + frame_length/2, frames)` as `librosa.feature.rms` does.
+/// Returns an error if the number of bins doesn't match the frame length
+pub fn spectrogram_rms<S, F>(
+    spectrogram: &ArrayBase<S, Ix2>,
+    frame_length: usize,
+) -> Result<Array1<F>, Error>
+where
+    S: Data<Elem = F>,
+    F: Float,
+{
+    let bins = spectrogram.nrows();
+    if frame_length == 0 || bins != 1 + frame_length / 2 {
+        return Err(Error::InvalidParameter(format!(
+            "a frame length of {} doesn't give {} frequency bins",
+            frame_length, bins
+        )));
+    }
+    let half = F::from(0.5).unwrap();
+    let n = F::from(frame_length).unwrap();
+    // The DC and, for even frame lengths, nyquist bins aren't mirrored in the
+    // negative frequencies
+    let nyquist = if 2 * (bins - 1) == frame_length {
+        bins - 1
+    } else {
+        0
+    };
+    Ok(spectrogram.map_axis(Axis(0), |col| {
+        let total = col.iter().enumerate().fold(F::zero(), |acc, (i, x)| {
+            let power = *x * *x;
+            if i == 0 || i == nyquist {
+                acc + half * power
+            } else {
+                acc + power
+            }
+        });
+        (total + total).sqrt() / n
+    }))
+}
+
+/// Calculate the signal to noise ratio in decibels of an estimate of the
+/// reference over the length of the shorter. Returns an error if either is
+/// empty or the reference is silent
+pub fn snr<'a, 'b, T, R, E>(reference: R, estimate: E) -> Result<f64, Error>
+where
+    T: Num + NumCast + Copy + 'a + 'b,
+    R: AsArray<'a, T>,
+    E: AsArray<'b, T>,
+{
+    let (reference, estimate) = aligned(reference.into(), estimate.into())?;
+    let noise = energy(reference.iter().zip(estimate.iter()).map(|(r, e)| r - e));
+    Ok(decibels(energy(reference.iter().copied()), noise))
+}
+
+/// Calculate the mean SNR of the frames of the signals, clamping each to
+/// between -10 and 35dB and skipping frames where the reference is silent.
+/// Returns an error if the hop or frame length is zero, the signals are shorter
+/// than a frame or the reference is silent in every frame
+pub fn segmental_snr<'a, 'b, T, R, E>(
+    reference: R,
+    estimate: E,
+    frame_length: usize,
+    hop_length: usize,
+) -> Result<f64, Error>
+where
+    T: Num + NumCast + Copy + 'a + 'b,
+    R: AsArray<'a, T>,
+    E: AsArray<'b, T>,
+{
+    let (reference, estimate) = aligned(reference.into(), estimate.into())?;
+    // Both are freshly converted so are contiguous
+    let reference = frame(reference.as_slice().unwrap(), frame_length, hop_length)?;
+    let estimate = frame(estimate.as_slice().unwrap(), frame_length, hop_length)?;
+    let (min, max) = SEGMENT_SNR_RANGE;
+    let (total, count) = reference
+        .axis_iter(Axis(1))
+        .zip(estimate.axis_iter(Axis(1)))
+        .map(|(r, e)| {
+            let signal = energy(r.iter().copied());
+            let noise = energy(r.iter().zip(e.iter()).map(|(r, e)| r - e));
+            (signal, noise)
+        })
+        .filter(|(signal, _)| *signal > 0.0)
+        .fold((0.0, 0), |(total, count), (signal, noise)| {
+            (total + decibels(signal, noise).clamp(min, max), count + 1)
+        });
+    if count == 0 {
+        return Err(Error::SilentReference);
+    }
+    Ok(total / count as f64)
+}
+
+/// Calculate the scale-invariant signal to distortion ratio in decibels of an
+/// estimate of the reference over the length of the shorter. Returns an error
+/// if either is empty or the reference is silent
+pub fn si_sdr<'a, 'b, T, R, E>(reference: R, estimate: E) -> Result<f64, Error>
+where
+    T: Num + NumCast + Copy + 'a + 'b,
+    R: AsArray<'a, T>,
+    E: AsArray<'b, T>,
+{
+    let (reference, estimate) = aligned(reference.into(), estimate.into())?;
+    let scale = reference.dot(&estimate) / energy(reference.iter().copied());
+    let target = energy(reference.iter().map(|r| scale * r));
+    let distortion = energy(
+        reference
+            .iter()
+            .zip(estimate.iter())
+            .map(|(r, e)| e - scale * r),
+    );
+    Ok(decibels(target, distortion))
+}
+
+/// Source to distortion, source to interference and source to artifacts ratios
+/// in decibels of an estimated source, as computed by `bss_eval`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BssEval {
+    /// Source to distortion ratio, accounting for all errors in the estimate
+    pub sdr: f64,
+    /// Source to interference ratio, measuring leakage of the other sources
+    pub sir: f64,
+    /// Source to artifacts ratio, measuring errors not explained by the sources
+    pub sar: f64,
+}
+
+/// Evaluate an estimate of one of the `(sources, samples)` references as
+/// `mir_eval.separation.bss_eval_sources` does without permuting, which uses a
+/// `filter_length` of 512. Returns an error if the source index is out of
+/// range, the filter length is zero or the references are silent or dependent
+pub fn bss_eval<'a, T, S, V>(
+    references: &ArrayBase<S, Ix2>,
+    estimate: V,
+    source: usize,
+    filter_length: usize,
+) -> Result<BssEval, Error>
+where
+    T: Num + NumCast + Copy + 'a,
+    S: Data<Elem = T>,
+    V: AsArray<'a, T>,
+{
+    if source >= references.nrows() {
+        return Err(Error::InvalidParameter(format!(
+            "source {} is out of range for {} references",
+            source,
+            references.nrows()
+        )));
+    }
+    if filter_length == 0 {
+        return Err(Error::InvalidParameter(
+            "filter length must be greater than zero".to_string(),
+        ));
+    }
+    let estimate = estimate.into();
+    let samples = references.ncols().min(estimate.len());
+    if samples == 0 {
+        return Err(Error::SignalTooShort {
+            samples,
+            required: 1,
+        });
+    }
+    let references = references
+        .slice(s![.., ..samples])
+        .mapv(|x| x.to_f64().unwrap_or_default());
+    if references.outer_iter().any(|x| x.iter().all(|x| *x == 0.0)) {
+        return Err(Error::SilentReference);
+    }
+    let estimate = to_f64(&estimate.slice(s![..samples]).to_vec());
+
+    // Correlations are computed through the FFT with enough padding that they
+    // don't wrap around
+    let n_fft = (samples + filter_length - 1).next_power_of_two();
+    let fft = RealFftPlanner::<f64>::new().plan_fft_forward(n_fft);
+    let spectrum = |signal: ArrayView1<f64>| {
+        let mut input = fft.make_input_vec();
+        let mut output = fft.make_output_vec();
+        for (i, x) in input.iter_mut().zip(signal.iter()) {
+            *i = *x;
+        }
+        fft.process(&mut input, &mut output)
+            .expect("buffers are sized by the planner");
+        output
+    };
+    let spectra = references.outer_iter().map(spectrum).collect::<Vec<_>>();
+    let estimate_spectrum = spectrum(aview1(&estimate));
+
+    let target = project(
+        references.slice(s![source..=source, ..]),
+        &spectra[source..=source],
+        &estimate_spectrum,
+        filter_length,
+    )?;
+    let all = project(
+        references.view(),
+        &spectra,
+        &estimate_spectrum,
+        filter_length,
+    )?;
+
+    let padded = |i: usize| estimate.get(i).copied().unwrap_or_default();
+    let target_energy = energy(target.iter().copied());
+    let distortion = energy(target.iter().enumerate().map(|(i, x)| padded(i) - x));
+    let interference = energy(target.iter().zip(all.iter()).map(|(t, a)| a - t));
+    let artifacts = energy(all.iter().enumerate().map(|(i, x)| padded(i) - x));
+    Ok(BssEval {
+        sdr: decibels(target_energy, distortion),
+        sir: decibels(target_energy, interference),
+        sar: decibels(energy(all.iter().copied()), artifacts),
+    })
+}
+
+/// Project the estimate onto the references delayed by up to
+/// `filter_length - 1` samples as mir_eval's `_project` does
+fn project(
+    references: ArrayView2<f64>,
+    spectra: &[Vec<Complex<f64>>],
+    estimate_spectrum: &[Complex<f64>],
+    filter_length: usize,
+) -> Result<Vec<f64>, Error> {
+    let n_fft = 2 * (estimate_spectrum.len() - 1);
+    let ifft = RealFftPlanner::<f64>::new().plan_fft_inverse(n_fft);
+    // `correlation(a, b)[d]` is the sum of `a[n + d] * b[n]` over `n`, with
+    // negative lags wrapping to the end
+    let correlation = |a: &[Complex<f64>], b: &[Complex<f64>]| {
+        let mut input = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| a * b.conj())
+            .collect::<Vec<_>>();
+        // The DC and nyquist bins of a real signal's spectrum are real
+        input[0].im = 0.0;
+        input[n_fft / 2].im = 0.0;
+        let mut output = ifft.make_output_vec();
+        ifft.process(&mut input, &mut output)
+            .expect("buffers are sized by the planner");
+        output.iter_mut().for_each(|x| *x /= n_fft as f64);
+        output
+    };
+    let lag = |c: &[f64], d: isize| c[d.rem_euclid(n_fft as isize) as usize];
+
+    let sources = references.nrows();
+    let size = sources * filter_length;
+    // Inner products between every pair of delayed references, and between
+    // the delayed references and the estimate
+    let mut gram = vec![0.0; size * size];
+    let mut inner = vec![0.0; size];
+    for i in 0..sources {
+        for j in 0..sources {
+            let c = correlation(&spectra[i], &spectra[j]);
+            for a in 0..filter_length {
+                let row = (i * filter_length + a) * size + j * filter_length;
+                for (b, g) in gram[row..row + filter_length].iter_mut().enumerate() {
+                    *g = lag(&c, b as isize - a as isize);
+                }
+            }
+        }
+        let c = correlation(&spectra[i], estimate_spectrum);
+        for (a, x) in inner[i * filter_length..(i + 1) * filter_length]
+            .iter_mut()
+            .enumerate()
+        {
+            *x = lag(&c, -(a as isize));
+        }
+    }
+    let filters = cholesky_solve(gram, inner, size).ok_or_else(|| {
+        Error::InvalidParameter("reference sources are linearly dependent".to_string())
+    })?;
+
+    let samples = references.ncols();
+    let mut projection = vec![0.0; samples + filter_length - 1];
+    for (reference, filter) in references
+        .outer_iter()
+        .zip(filters.chunks_exact(filter_length))
+    {
+        for (delay, coefficient) in filter.iter().enumerate() {
+            for (p, x) in projection[delay..].iter_mut().zip(reference.iter()) {
+                *p += coefficient * x;
+            }
+        }
+    }
+    Ok(projection)
+}
+
+/// Solve `Ax = b` for a symmetric positive definite `n x n` row major matrix,
+/// returning `None` if it isn't positive definite
+fn cholesky_solve(mut a: Vec<f64>, mut b: Vec<f64>, n: usize) -> Option<Vec<f64>> {
+    // Factorise into `LL^T` in place, with `L` in the lower triangle
+    for j in 0..n {
+        let (row_j, below) = a[j * n..].split_at_mut(n);
+        let diagonal = row_j[j] - row_j[..j].iter().map(|x| x * x).sum::<f64>();
+        if diagonal.is_nan() || diagonal <= 1e-12 * row_j[j].abs() {
+            return None;
+        }
+        row_j[j] = diagonal.sqrt();
+        for row_i in below.chunks_exact_mut(n) {
+            let dot = row_i[..j]
+                .iter()
+                .zip(row_j[..j].iter())
+                .map(|(x, y)| x * y)
+                .sum::<f64>();
+            row_i[j] = (row_i[j] - dot) / row_j[j];
+        }
+    }
+    // Forward substitution with `L` then back substitution with `L^T`
+    for i in 0..n {
+        let row = &a[i * n..i * n + i];
+        let dot = row.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f64>();
+        b[i] = (b[i] - dot) / a[i * n + i];
+    }
+    for i in (0..n).rev() {
+        let dot = (i + 1..n).map(|k| a[k * n + i] * b[k]).sum::<f64>();
+        b[i] = (b[i] - dot) / a[i * n + i];
+    }
+    Some(b)
+}
+
+/// Truncate the signals to the same length and convert them to doubles
+fn aligned<T: Num + NumCast + Copy>(
+    reference: ArrayView1<T>,
+    estimate: ArrayView1<T>,
+) -> Result<(Array1<f64>, Array1<f64>), Error> {
+    let samples = reference.len().min(estimate.len());
+    if samples == 0 {
+        return Err(Error::SignalTooShort {
+            samples,
+            required: 1,
+        });
+    }
+    let convert = |x: &T| x.to_f64().unwrap_or_default();
+    let reference = reference.slice(s![..samples]).map(convert);
+    if reference.iter().all(|x| *x == 0.0) {
+        return Err(Error::SilentReference);
+    }
+    Ok((reference, estimate.slice(s![..samples]).map(convert)))
+}
+
+fn energy<I: Iterator<Item = f64>>(samples: I) -> f64 {
+    samples.map(|x| x * x).sum()
+}
+
+/// Ratio of two energies in decibels, infinite if the denominator is zero
+fn decibels(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (numerator / denominator).log10()
+    }
+}
+
+fn to_f64<T: Num + NumCast>(samples: &[T]) -> Vec<f64> {
+    samples
+        .iter()
+        .map(|x| x.to_f64().unwrap_or_default())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spectrum::FrequencyComponents;
+    use crate::stft::StftBuilder;
+    use crate::window::WindowingAlgorithm;
     use float_cmp::approx_eq;
 
     #[test]
@@ -30,7 +445,7 @@ mod tests {
         );
         let data = vec![0, 1, 2, 3, 4, 5];
         let power_act = power(data.as_slice());
-        let expected = 9.166666666666666666;
+        let expected = 9.166_666_666_666_666;
         assert!(
             approx_eq!(f64, power_act, expected, ulps = 3),
             "{} ~= {}",
@@ -51,4 +466,198 @@ mod tests {
             rms_act
         );
     }
+
+    #[test]
+    fn framewise_metrics() {
+        let signal = vec![1.0f32; 8];
+        let rms = frame_rms(&signal, 4, 2, PadMode::Zeros).unwrap();
+        let half = 0.5f64.sqrt();
+        assert_eq!(rms, array![half, 1.0, 1.0, 1.0, half]);
+        let rms = frame_rms(&signal, 4, 2, PadMode::NoPad).unwrap();
+        assert_eq!(rms, array![1.0, 1.0, 1.0]);
+        let energy = frame_energy(&[1i16, -2, 3, -4, 5], 2, 2, PadMode::NoPad).unwrap();
+        assert_eq!(energy, array![5.0, 25.0]);
+
+        let zcr = zero_crossing_rate(&[1, -1, 1, -1, 1, 1, 1, 1], 4, 4, PadMode::NoPad).unwrap();
+        assert_eq!(zcr, array![0.75, 0.0]);
+        // Values within 1e-10 of zero count as positive
+        let zcr = zero_crossing_rate(&[0.0, -1e-11, 1.0, -1.0], 4, 1, PadMode::NoPad).unwrap();
+        assert_eq!(zcr, array![0.25]);
+        // Edge padding repeats the first and last samples without crossings
+        let zcr = zero_crossing_rate(&[1.0, -1.0], 4, 1, PadMode::Edge).unwrap();
+        assert_eq!(zcr, array![0.25, 0.25, 0.25]);
+
+        assert!(frame_rms(&signal, 4, 0, PadMode::Zeros).is_err());
+        assert!(zero_crossing_rate(&signal, 9, 1, PadMode::NoPad).is_err());
+    }
+
+    #[test]
+    fn rms_from_spectrogram() {
+        let signal = (0..500)
+            .map(|x| (x as f32 * 0.3).sin() + 0.1 * (x as f32 * 1.7).cos())
+            .collect::<Vec<_>>();
+        for n_fft in [64, 63].iter() {
+            let stft = StftBuilder::new()
+                .set_fft_num(*n_fft)
+                .set_hop_len(16)
+                .set_windowing_algorithm(WindowingAlgorithm::Rectangular)
+                .build();
+            let spectrogram = stft.run(&signal).unwrap().mag();
+            let rms = spectrogram_rms(&spectrogram, *n_fft).unwrap();
+            let expected = frame_rms(&signal, *n_fft, 16, PadMode::Zeros).unwrap();
+            assert_eq!(rms.len(), expected.len());
+            for (a, e) in rms.iter().zip(expected.iter()) {
+                assert!(
+                    approx_eq!(f64, *a as f64, *e, epsilon = 1e-5),
+                    "{} ~= {}",
+                    a,
+                    e
+                );
+            }
+            assert!(spectrogram_rms(&spectrogram, n_fft + 2).is_err());
+        }
+    }
+
+    #[test]
+    fn reference_metrics() {
+        let reference = [1.0, 1.0, 1.0, 1.0];
+        let estimate = [2.0, 3.0, 2.0, 1.0];
+        // Noise of [-1, -2, -1, 0]
+        let expected = 10.0 * (4.0f64 / 6.0).log10();
+        assert!(approx_eq!(
+            f64,
+            snr(&reference[..], &estimate[..]).unwrap(),
+            expected,
+            ulps = 4
+        ));
+        // The reference scaled by 2 leaves [0, 1, 0, -1] as distortion
+        let expected = 10.0 * 8.0f64.log10();
+        let result = si_sdr(&reference[..], &estimate[..]).unwrap();
+        assert!(
+            approx_eq!(f64, result, expected, ulps = 4),
+            "{} ~= {}",
+            result,
+            expected
+        );
+        let scaled = estimate.iter().map(|x| x * -0.1).collect::<Vec<_>>();
+        let result = si_sdr(&reference[..], &scaled[..]).unwrap();
+        assert!(
+            approx_eq!(f64, result, expected, epsilon = 1e-9),
+            "{} ~= {}",
+            result,
+            expected
+        );
+
+        // Arrays and integers work and extra samples are ignored
+        let reference = array![1, 2, 3];
+        let estimate = array![1, 2, 3, 4, 5];
+        assert_eq!(snr(&reference, &estimate), Ok(f64::INFINITY));
+        assert_eq!(si_sdr(reference.view(), &estimate), Ok(f64::INFINITY));
+
+        let silent = [0.0; 4];
+        assert_eq!(snr(&silent[..], &[1.0; 4][..]), Err(Error::SilentReference));
+        assert_eq!(
+            si_sdr(&silent[..], &silent[..]),
+            Err(Error::SilentReference)
+        );
+        assert!(matches!(
+            snr(&silent[..0], &silent[..]),
+            Err(Error::SignalTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn segmental_metrics() {
+        let reference = [1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+        let estimate = [1.0, 1.0, 1.0, 0.0, 1.0, 0.9, 0.0, 1.0];
+        // The perfect frame is clamped to 35dB, the silent frame is skipped and
+        // the others have noise energies of 0.01 and 1
+        let result = segmental_snr(&reference[..], &estimate[..], 2, 2).unwrap();
+        let expected =
+            (35.0 + 10.0 * (2.0f64 / 0.01).log10() + 10.0 * (2.0f64 / 1.0).log10()) / 3.0;
+        assert!(
+            approx_eq!(f64, result, expected, epsilon = 1e-9),
+            "{} ~= {}",
+            result,
+            expected
+        );
+
+        let inverted = reference.iter().map(|x| -10.0 * x).collect::<Vec<_>>();
+        let result = segmental_snr(&reference[..], &inverted[..], 4, 2).unwrap();
+        assert_eq!(result, -10.0);
+
+        assert_eq!(
+            segmental_snr(&[0.0, 0.0, 0.0, 1.0][..], &[1.0, 1.0, 0.0, 1.0][..], 2, 1),
+            Ok(35.0)
+        );
+        assert_eq!(
+            segmental_snr(&[0.0, 0.0, 0.0, 1.0][..], &[1.0; 4][..], 2, 2),
+            Ok(0.0)
+        );
+        assert_eq!(
+            segmental_snr(&[0.0; 4][..], &[1.0; 4][..], 2, 2),
+            Err(Error::SilentReference)
+        );
+        assert!(segmental_snr(&reference[..], &estimate[..], 9, 2).is_err());
+    }
+
+    #[test]
+    fn bss_eval_decomposition() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut references = Array2::from_shape_fn((2, 2000), |_| rng.gen::<f64>() - 0.5);
+        // Delayed copies of the references are truncated to the same length
+        // unless they end in silence
+        references.slice_mut(s![.., 1990..]).fill(0.0);
+
+        // A delayed and scaled copy of the source is explained by the filter
+        let mut delayed = Array1::zeros(2000);
+        delayed
+            .slice_mut(s![3..])
+            .assign(&references.slice(s![0, ..1997]));
+        let estimate = delayed.mapv(|x| 0.5 * x);
+        let result = bss_eval(&references, &estimate, 0, 8).unwrap();
+        assert!(result.sdr > 100.0, "{:?}", result);
+        assert!(result.sar > 100.0, "{:?}", result);
+
+        // Leakage of the other source is interference
+        let estimate = &references.row(0) + &references.row(1).mapv(|x| 0.1 * x);
+        let result = bss_eval(&references, &estimate, 0, 8).unwrap();
+        assert!(
+            approx_eq!(f64, result.sir, 20.0, epsilon = 0.5),
+            "{:?}",
+            result
+        );
+        assert!(
+            approx_eq!(f64, result.sdr, result.sir, epsilon = 1e-6),
+            "{:?}",
+            result
+        );
+        assert!(result.sar > 100.0, "{:?}", result);
+        // With only one reference the leakage can only be artifacts
+        let result = bss_eval(&references.slice(s![..1, ..]), &estimate, 0, 8).unwrap();
+        assert_eq!(result.sir, f64::INFINITY);
+        assert!(
+            approx_eq!(f64, result.sdr, result.sar, epsilon = 1e-9),
+            "{:?}",
+            result
+        );
+        assert!(
+            approx_eq!(f64, result.sar, 20.0, epsilon = 0.5),
+            "{:?}",
+            result
+        );
+
+        let mut silent = references.clone();
+        silent.row_mut(1).fill(0.0);
+        assert_eq!(
+            bss_eval(&silent, &estimate, 0, 8),
+            Err(Error::SilentReference)
+        );
+        let mut repeated = references.clone();
+        repeated.row_mut(1).assign(&references.row(0));
+        assert!(bss_eval(&repeated, &estimate, 0, 8).is_err());
+        assert!(bss_eval(&references, &estimate, 2, 8).is_err());
+        assert!(bss_eval(&references, &estimate, 0, 0).is_err());
+    }
 }
@@ -0,0 +1,357 @@
+use crate::decibel::{power_to_db, DbReference};
+use crate::error::Error;
+use crate::filterbank::{MelFilterbank, MelFilterbankBuilder};
+use crate::spectrum::SpectrumExt;
+use crate::speech_features::{SpeechFeatures, SpeechFeaturesBuilder};
+use crate::stft::{ShortTimeFourierTransform, StftBuilder};
+use ndarray::prelude::*;
+use num_traits::{Bounded, Num, NumCast};
+use std::f32::consts::PI;
+
+/// The reference implementation an MFCC configuration reproduces. This
+/// determines how the signal is framed, which filterbank is used, how the
+/// filterbank energies are compressed and how the cepstra are liftered
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MfccPreset {
+    /// Reproduces `librosa.feature.mfcc`. The mel spectrogram is converted to
+    /// decibels with an 80dB dynamic range before the DCT
+    Librosa,
+    /// Reproduces `python_speech_features.mfcc`. The signal is preemphasised,
+    /// framed without centring and the log filterbank energies are used
+    SpeechFeatures,
+}
+
+/// Builds an Mfcc instance for one of the presets. Any parameters not set
+/// take the defaults of the reference implementation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MfccBuilder {
+    preset: MfccPreset,
+    sample_rate: f32,
+    n_mfcc: Option<usize>,
+    n_mels: Option<usize>,
+    n_fft: Option<usize>,
+    win_length: Option<usize>,
+    hop_length: Option<usize>,
+    fmin: Option<f32>,
+    fmax: Option<f32>,
+    lifter: Option<usize>,
+    preemphasis: Option<f32>,
+    append_energy: Option<bool>,
+}
+
+impl MfccBuilder {
+    fn new(preset: MfccPreset, sample_rate: f32) -> Self {
+        Self {
+            preset,
+            sample_rate,
+            n_mfcc: None,
+            n_mels: None,
+            n_fft: None,
+            win_length: None,
+            hop_length: None,
+            fmin: None,
+            fmax: None,
+            lifter: None,
+            preemphasis: None,
+            append_energy: None,
+        }
+    }
+
+    /// Create a builder reproducing `librosa.feature.mfcc` for a signal with
+    /// the given sample rate
+    pub fn librosa(sample_rate: f32) -> Self {
+        Self::new(MfccPreset::Librosa, sample_rate)
+    }
+
+    /// Create a builder reproducing `python_speech_features.mfcc` for a
+    /// signal with the given sample rate
+    pub fn python_speech_features(sample_rate: f32) -> Self {
+        Self::new(MfccPreset::SpeechFeatures, sample_rate)
+    }
+
+    /// Set the number of cepstral coefficients to return
+    pub fn set_mfcc_num(mut self, n: usize) -> Self {
+        self.n_mfcc = Some(n);
+        self
+    }
+
+    /// Set the number of mel filters
+    pub fn set_mel_num(mut self, n: usize) -> Self {
+        self.n_mels = Some(n);
+        self
+    }
+
+    /// Set the number of FFT bins
+    pub fn set_fft_num(mut self, n: usize) -> Self {
+        self.n_fft = Some(n);
+        self
+    }
+
+    /// Set the length of each frame in samples
+    pub fn set_window_len(mut self, n: usize) -> Self {
+        self.win_length = Some(n);
+        self
+    }
+
+    /// Set the number of samples between successive frames
+    pub fn set_hop_len(mut self, n: usize) -> Self {
+        self.hop_length = Some(n);
+        self
+    }
+
+    /// Set the lowest frequency of the mel filters in Hz
+    pub fn set_fmin(mut self, fmin: f32) -> Self {
+        self.fmin = Some(fmin);
+        self
+    }
+
+    /// Set the highest frequency of the mel filters in Hz
+    pub fn set_fmax(mut self, fmax: f32) -> Self {
+        self.fmax = Some(fmax);
+        self
+    }
+
+    /// Set the cepstral lifter coefficient, 0 disables liftering
+    pub fn set_lifter(mut self, lifter: usize) -> Self {
+        self.lifter = Some(lifter);
+        self
+    }
+
+    /// Set the preemphasis coefficient. This is only used by the
+    /// python_speech_features preset, 0 disables preemphasis
+    pub fn set_preemphasis(mut self, coefficient: f32) -> Self {
+        self.preemphasis = Some(coefficient);
+        self
+    }
+
+    /// Sets whether the zeroth cepstral coefficient is replaced with the log
+    /// of the total frame energy. This is only used by the
+    /// python_speech_features preset
+    pub fn set_append_energy(mut self, append_energy: bool) -> Self {
+        self.append_energy = Some(append_energy);
+        self
+    }
+
+    /// Build the Mfcc instance. For the librosa preset the defaults are
+    ///
+    /// n_mfcc default 20
+    /// n_mels default 128
+    /// n_fft default 2048
+    /// win_length default n_fft
+    /// hop_length default win_length/4
+    /// lifter default 0
+    ///
+    /// For the python_speech_features preset the defaults are
+    ///
+    /// n_mfcc default 13
+    /// n_mels default 26
+    /// n_fft default 512
+    /// win_length default 25ms
+    /// hop_length default 10ms
+    /// lifter default 22
+    /// preemphasis default 0.97
+    /// append_energy default true
+    ///
+    /// In both cases the filters span from 0Hz to half the sample rate
+    pub fn build(self) -> Mfcc {
+        let fmin = self.fmin.unwrap_or(0.0);
+        let fmax = self.fmax.unwrap_or(self.sample_rate / 2.0);
+        match self.preset {
+            MfccPreset::Librosa => {
+                let n_mfcc = self.n_mfcc.unwrap_or(20);
+                let n_fft = self.n_fft.unwrap_or(2048);
+                let mut stft = StftBuilder::new().set_fft_num(n_fft);
+                if let Some(win_length) = self.win_length {
+                    stft = stft.set_window_len(win_length);
+                }
+                if let Some(hop_length) = self.hop_length {
+                    stft = stft.set_hop_len(hop_length);
+                }
+                let filterbank = MelFilterbankBuilder::new(self.sample_rate, n_fft)
+                    .set_mel_num(self.n_mels.unwrap_or(128))
+                    .set_fmin(fmin)
+                    .set_fmax(fmax)
+                    .build();
+                let dct = dct_matrix(n_mfcc, filterbank.n_mels());
+                let lifter = lifter_weights(n_mfcc, self.lifter.unwrap_or(0) as f32, 1);
+                Mfcc {
+                    energies: FilterbankEnergies::Librosa {
+                        stft: stft.build(),
+                        filterbank,
+                    },
+                    dct,
+                    lifter,
+                }
+            }
+            MfccPreset::SpeechFeatures => {
+                let n_mfcc = self.n_mfcc.unwrap_or(13);
+                let n_mels = self.n_mels.unwrap_or(26);
+                // The frame lengths are given to python_speech_features in
+                // seconds, which are rounded back to the nearest sample
+                let mut features = SpeechFeaturesBuilder::new(self.sample_rate)
+                    .set_filter_num(n_mels)
+                    .set_fft_num(self.n_fft.unwrap_or(512))
+                    .set_lowfreq(fmin)
+                    .set_highfreq(fmax)
+                    .set_preemphasis(self.preemphasis.unwrap_or(0.97));
+                if let Some(win_length) = self.win_length {
+                    features = features.set_winlen(win_length as f32 / self.sample_rate);
+                }
+                if let Some(hop_length) = self.hop_length {
+                    features = features.set_winstep(hop_length as f32 / self.sample_rate);
+                }
+                let dct = dct_matrix(n_mfcc, n_mels);
+                let lifter = lifter_weights(n_mfcc, self.lifter.unwrap_or(22) as f32, 0);
+                Mfcc {
+                    energies: FilterbankEnergies::SpeechFeatures {
+                        features: features.build(),
+                        append_energy: self.append_energy.unwrap_or(true),
+                    },
+                    dct,
+                    lifter,
+                }
+            }
+        }
+    }
+}
+
+/// How each preset computes the filterbank energies the DCT is applied to
+#[derive(Clone, Debug, PartialEq)]
+enum FilterbankEnergies {
+    Librosa {
+        stft: ShortTimeFourierTransform,
+        filterbank: MelFilterbank,
+    },
+    SpeechFeatures {
+        features: SpeechFeatures,
+        append_energy: bool,
+    },
+}
+
+/// Computes mel frequency cepstral coefficients. Build using the MfccBuilder
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mfcc {
+    energies: FilterbankEnergies,
+    dct: Array2<f32>,
+    lifter: Array1<f32>,
+}
+
+impl Mfcc {
+    /// The preset this instance reproduces
+    pub fn preset(&self) -> MfccPreset {
+        match self.energies {
+            FilterbankEnergies::Librosa { .. } => MfccPreset::Librosa,
+            FilterbankEnergies::SpeechFeatures { .. } => MfccPreset::SpeechFeatures,
+        }
+    }
+
+    /// Compute the MFCCs of the signal returning an array of shape
+    /// `(n_mfcc, frames)`. Returns an error if the signal is too short to be
+    /// transformed
+    pub fn run<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Result<Array2<f32>, Error> {
+        let mut cepstra = match &self.energies {
+            FilterbankEnergies::Librosa { stft, filterbank } => {
+                let mel = ArrayView1::from(samples).melspectrogram(stft, filterbank, Some(2.0))?;
+                let db = power_to_db(&mel, DbReference::default(), 1e-10, Some(80.0))?;
+                self.dct.dot(&db)
+            }
+            FilterbankEnergies::SpeechFeatures {
+                features,
+                append_energy,
+            } => {
+                let (feat, energy) = features.fbank(samples)?;
+                let mut cepstra = self.dct.dot(&feat.mapv(f32::ln));
+                if *append_energy {
+                    cepstra.row_mut(0).assign(&energy.mapv(f32::ln));
+                }
+                cepstra
+            }
+        };
+        cepstra *= &self.lifter.view().insert_axis(Axis(1));
+        Ok(cepstra)
+    }
+}
+
+/// Create the matrix for an orthonormal DCT-II of `n_input` points keeping the
+/// first `n_output` coefficients. This has shape `(n_output, n_input)`
+pub fn dct_matrix(n_output: usize, n_input: usize) -> Array2<f32> {
+    let n = n_input as f64;
+    Array2::from_shape_fn((n_output, n_input), |(k, i)| {
+        let scale = if k == 0 {
+            (1.0 / n).sqrt()
+        } else {
+            (2.0 / n).sqrt()
+        };
+        let angle = std::f64::consts::PI * k as f64 * (2 * i + 1) as f64 / (2.0 * n);
+        (scale * angle.cos()) as f32
+    })
+}
+
+/// Sinusoidal lifter weights `1 + (L/2) sin(pi * (k + offset) / L)` for each
+/// coefficient `k`. librosa counts coefficients from 1 and
+/// python_speech_features and Kaldi from 0, so the zeroth coefficient is only
+/// liftered by librosa. A lifter of 0 gives unit weights
+pub(crate) fn lifter_weights(n_mfcc: usize, lifter: f32, offset: usize) -> Array1<f32> {
+    if lifter == 0.0 {
+        Array1::ones(n_mfcc)
+    } else {
+        Array1::from_shape_fn(n_mfcc, |k| {
+            1.0 + (lifter / 2.0) * (PI * (k + offset) as f32 / lifter).sin()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn orthonormal_dct() {
+        let dct = dct_matrix(4, 4);
+        let result = dct.dot(&array![1.0, 2.0, 3.0, 4.0]);
+        // Generated via scipy.fftpack.dct([1, 2, 3, 4], norm='ortho')
+        let expected = [5.0, -2.2304425, 0.0, -0.15851267];
+        for (a, e) in result.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-6), "{} ~= {}", a, e);
+        }
+
+        // Orthonormal so the transpose is the inverse
+        let identity = dct.dot(&dct.t());
+        for ((i, j), x) in identity.indexed_iter() {
+            let e = if i == j { 1.0 } else { 0.0 };
+            assert!(approx_eq!(f32, *x, e, epsilon = 1e-6));
+        }
+    }
+
+    #[test]
+    fn lifter_offsets() {
+        assert_eq!(lifter_weights(3, 0.0, 0), array![1.0, 1.0, 1.0]);
+        let psf = lifter_weights(3, 2.0, 0);
+        let librosa = lifter_weights(3, 2.0, 1);
+        assert!(approx_eq!(f32, psf[0], 1.0));
+        assert!(approx_eq!(f32, psf[1], 2.0));
+        assert!(approx_eq!(f32, librosa[0], 2.0));
+        assert!(approx_eq!(f32, librosa[1], 1.0, epsilon = 1e-6));
+    }
+
+    #[test]
+    fn mfcc_shapes() {
+        let signal = (0..16000)
+            .map(|x| (x as f32 * 0.05).sin())
+            .collect::<Vec<_>>();
+
+        let librosa = MfccBuilder::librosa(16000.0).build();
+        let result = librosa.run(&signal).unwrap();
+        // 1 + 16000/512 frames
+        assert_eq!(result.dim(), (20, 32));
+
+        let psf = MfccBuilder::python_speech_features(16000.0)
+            .set_mfcc_num(12)
+            .build();
+        let result = psf.run(&signal).unwrap();
+        // 1 + ceil((16000 - 400)/160) frames
+        assert_eq!(result.dim(), (12, 99));
+        assert!(result.iter().all(|x| x.is_finite()));
+    }
+}
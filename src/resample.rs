@@ -0,0 +1,547 @@
+use crate::error::Error;
+use crate::stft::{to_floats, StftFloat};
+use crate::window::bessel_i0;
+use ndarray::{prelude::*, Data};
+use num_traits::{Bounded, Num, NumCast};
+use std::f64::consts::PI;
+use std::marker::PhantomData;
+
+/// The interpolation filter used to resample, a Kaiser windowed sinc. The
+/// presets match the filters of the same name in resampy
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ResampleFilter {
+    /// resampy's `kaiser_fast`, 16 zero crossings with a rolloff of 0.85 and
+    /// a Kaiser beta of 8.555504641634386
+    KaiserFast,
+    /// resampy's `kaiser_best`, 64 zero crossings with a rolloff of
+    /// 0.9475937167399596 and a Kaiser beta of 14.769656459379492. This is the
+    /// resampy default
+    #[default]
+    KaiserBest,
+    /// A custom Kaiser windowed sinc
+    Kaiser {
+        /// Number of zero crossings of the sinc either side of its peak, more
+        /// gives a sharper cutoff at the cost of speed
+        zero_crossings: usize,
+        /// The filter is tabulated at `2^precision` points per zero crossing
+        precision: u32,
+        /// Cutoff frequency as a fraction of the nyquist frequency
+        rolloff: f64,
+        /// Shape parameter of the Kaiser window
+        beta: f64,
+    },
+}
+
+impl ResampleFilter {
+    /// The zero crossings, precision, rolloff and beta of the filter
+    fn parameters(self) -> (usize, u32, f64, f64) {
+        match self {
+            Self::KaiserFast => (16, 9, 0.85, 8.555504641634386),
+            Self::KaiserBest => (64, 9, 0.9475937167399596, 14.769656459379492),
+            Self::Kaiser {
+                zero_crossings,
+                precision,
+                rolloff,
+                beta,
+            } => (zero_crossings, precision, rolloff, beta),
+        }
+    }
+}
+
+/// Builds a Resampler converting between two sample rates
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ResamplerBuilder<F = f32> {
+    orig_sr: f32,
+    target_sr: f32,
+    filter: Option<ResampleFilter>,
+    precision: PhantomData<F>,
+}
+
+impl ResamplerBuilder {
+    /// Create a builder converting from `orig_sr` to `target_sr` in single
+    /// precision. Use `with_rates` for other float types
+    pub fn new(orig_sr: f32, target_sr: f32) -> Self {
+        Self::with_rates(orig_sr, target_sr)
+    }
+}
+
+impl<F: StftFloat> ResamplerBuilder<F> {
+    /// Create a builder converting from `orig_sr` to `target_sr`, producing
+    /// samples of any supported float type
+    pub fn with_rates(orig_sr: f32, target_sr: f32) -> Self {
+        Self {
+            orig_sr,
+            target_sr,
+            filter: None,
+            precision: PhantomData,
+        }
+    }
+
+    /// Set the interpolation filter
+    pub fn set_filter(mut self, filter: ResampleFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Build the Resampler instance. The filter defaults to `KaiserBest`
+    ///
+    /// # Panics
+    ///
+    /// If the parameters are invalid, see `try_build` for a fallible version
+    pub fn build(self) -> Resampler<F> {
+        match self.try_build() {
+            Ok(resampler) => resampler,
+            Err(e) => panic!("Invalid resampling parameters: {}", e),
+        }
+    }
+
+    /// Build the Resampler instance using the same defaults as `build`,
+    /// returning an error if either sample rate isn't positive, the ratio is
+    /// too small for the filter's precision or a custom filter has no zero
+    /// crossings or a rolloff outside `(0, 1]`
+    pub fn try_build(self) -> Result<Resampler<F>, Error> {
+        if !(self.orig_sr > 0.0 && self.target_sr > 0.0) {
+            return Err(Error::InvalidParameter(
+                "sample rates must be positive".to_string(),
+            ));
+        }
+        let filter = self.filter.unwrap_or_default();
+        let (zero_crossings, precision, rolloff, beta) = filter.parameters();
+        if zero_crossings == 0 || precision > 24 || !(rolloff > 0.0 && rolloff <= 1.0) {
+            return Err(Error::InvalidParameter(format!(
+                "invalid resampling filter {:?}",
+                filter
+            )));
+        }
+
+        let ratio = self.target_sr as f64 / self.orig_sr as f64;
+        let scale = ratio.min(1.0);
+        let num_table = (1usize << precision) as f64;
+        let index_step = (scale * num_table) as usize;
+        if index_step == 0 {
+            return Err(Error::InvalidParameter(format!(
+                "ratio {} is too small for a filter precision of {}",
+                ratio, precision
+            )));
+        }
+
+        // When downsampling the filter is stretched to the new nyquist
+        // frequency and scaled to keep unit gain
+        let window = sinc_window(zero_crossings, precision, rolloff, beta)
+            .into_iter()
+            .map(|x| x * scale)
+            .collect::<Vec<_>>();
+        let delta = window
+            .windows(2)
+            .map(|x| x[1] - x[0])
+            .chain(std::iter::once(0.0))
+            .collect::<Vec<_>>();
+        let to_float = |x: Vec<f64>| {
+            x.into_iter()
+                .map(|x| F::from_f64(x).unwrap())
+                .collect::<Vec<_>>()
+        };
+        Ok(Resampler {
+            orig_sr: self.orig_sr,
+            target_sr: self.target_sr,
+            ratio,
+            scale,
+            num_table,
+            index_step,
+            window: to_float(window),
+            delta: to_float(delta),
+        })
+    }
+}
+
+/// Converts signals between sample rates by band-limited interpolation with a
+/// tabulated windowed sinc filter. This reproduces `resampy.resample` for any
+/// ratio of sample rates. Build using the ResamplerBuilder
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resampler<F = f32> {
+    orig_sr: f32,
+    target_sr: f32,
+    ratio: f64,
+    scale: f64,
+    num_table: f64,
+    index_step: usize,
+    /// Right half of the filter, tabulated at `num_table` points per zero
+    /// crossing
+    window: Vec<F>,
+    /// Difference between consecutive filter values, for linear
+    /// interpolation between the tabulated points
+    delta: Vec<F>,
+}
+
+impl<F: StftFloat> Resampler<F> {
+    /// The sample rate of the input
+    pub fn orig_sr(&self) -> f32 {
+        self.orig_sr
+    }
+
+    /// The sample rate of the output
+    pub fn target_sr(&self) -> f32 {
+        self.target_sr
+    }
+
+    /// The number of output samples produced from `samples` input samples
+    pub fn output_len(&self, samples: usize) -> usize {
+        (samples as f64 * self.ratio) as usize
+    }
+
+    /// Resample the signal. Returns an error if the signal is too short to
+    /// produce any output samples
+    pub fn run<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Result<Array1<F>, Error> {
+        let mut signal = vec![];
+        to_floats(samples, &mut signal);
+        let output_len = self.check_len(signal.len())?;
+        Ok((0..output_len)
+            .map(|t| self.interpolate(&signal, 0, signal.len(), t))
+            .collect())
+    }
+
+    /// Resample every channel of a signal with the samples along the last
+    /// axis, such as `(channels, samples)`. The output has the same shape
+    /// apart from the number of samples
+    pub fn run_multichannel<S, T, D>(&self, signal: &ArrayBase<S, D>) -> Result<Array<F, D>, Error>
+    where
+        S: Data<Elem = T>,
+        T: Num + Bounded + NumCast,
+        D: Dimension,
+    {
+        let ndim = signal.ndim();
+        if ndim == 0 {
+            return Err(Error::InvalidParameter(
+                "signal must have a samples axis".to_string(),
+            ));
+        }
+        let samples = signal.len_of(Axis(ndim - 1));
+        let output_len = self.check_len(samples)?;
+        let mut dim = signal.raw_dim();
+        dim[ndim - 1] = output_len;
+        let mut result = Array::zeros(dim);
+        let mut data = Vec::with_capacity(samples);
+        for (lane, mut out) in signal
+            .lanes(Axis(ndim - 1))
+            .into_iter()
+            .zip(result.lanes_mut(Axis(ndim - 1)))
+        {
+            data.clear();
+            data.extend(
+                lane.iter()
+                    .map(|x| x.to_f64().and_then(F::from_f64).unwrap_or_default()),
+            );
+            for (t, y) in out.iter_mut().enumerate() {
+                *y = self.interpolate(&data, 0, samples, t);
+            }
+        }
+        Ok(result)
+    }
+
+    fn check_len(&self, samples: usize) -> Result<usize, Error> {
+        let output_len = self.output_len(samples);
+        if output_len == 0 {
+            Err(Error::SignalTooShort {
+                samples,
+                required: self.ratio.recip().ceil() as usize,
+            })
+        } else {
+            Ok(output_len)
+        }
+    }
+
+    /// Position of output sample `t` in the input, computed as resampy does
+    fn input_time(&self, t: usize) -> f64 {
+        t as f64 * self.ratio.recip()
+    }
+
+    /// Number of input samples either side of an output sample that
+    /// contribute to it
+    fn reach(&self) -> usize {
+        self.window.len() / self.index_step
+    }
+
+    /// Compute output sample `t` of a signal of `len` samples, where `signal`
+    /// holds the input from sample `start` onwards
+    fn interpolate(&self, signal: &[F], start: usize, len: usize, t: usize) -> F {
+        let time = self.input_time(t);
+        let n = time as usize;
+        let frac = self.scale * (time - n as f64);
+        let nwin = self.window.len();
+        let mut y = F::zero();
+
+        // Left wing of the filter over the current and earlier samples
+        let index_frac = frac * self.num_table;
+        let offset = index_frac as usize;
+        let eta = F::from_f64(index_frac - offset as f64).unwrap();
+        let i_max = (n + 1).min((nwin - offset) / self.index_step);
+        for i in 0..i_max {
+            let k = offset + i * self.index_step;
+            y = y + (self.window[k] + eta * self.delta[k]) * signal[n - i - start];
+        }
+
+        // Right wing over the later samples
+        let index_frac = (self.scale - frac) * self.num_table;
+        let offset = index_frac as usize;
+        let eta = F::from_f64(index_frac - offset as f64).unwrap();
+        let k_max = (len - n - 1).min((nwin - offset) / self.index_step);
+        for k in 0..k_max {
+            let j = offset + k * self.index_step;
+            y = y + (self.window[j] + eta * self.delta[j]) * signal[n + k + 1 - start];
+        }
+        y
+    }
+}
+
+/// Incremental resampling for live audio. Samples can be pushed in chunks of
+/// any size and every output sample whose filter lies within the samples seen
+/// so far is returned. Calling `flush` at the end of the signal returns the
+/// remaining samples, so the outputs of all the pushes and the flush
+/// concatenated are the same as `run` on the whole signal
+#[derive(Clone, Debug)]
+pub struct StreamingResampler<F = f32> {
+    resampler: Resampler<F>,
+    /// Samples still needed by the filter, starting at sample `offset`
+    buffer: Vec<F>,
+    offset: usize,
+    /// Number of samples pushed since the start of the signal
+    received: usize,
+    /// Index of the next output sample
+    next_output: usize,
+    scratch: Vec<F>,
+}
+
+impl<F: StftFloat> From<Resampler<F>> for StreamingResampler<F> {
+    fn from(resampler: Resampler<F>) -> Self {
+        Self::new(resampler)
+    }
+}
+
+impl<F: StftFloat> StreamingResampler<F> {
+    /// Create a streaming resampler using the given resampler
+    pub fn new(resampler: Resampler<F>) -> Self {
+        Self {
+            resampler,
+            buffer: vec![],
+            offset: 0,
+            received: 0,
+            next_output: 0,
+            scratch: vec![],
+        }
+    }
+
+    /// The resampler the output is computed with
+    pub fn resampler(&self) -> &Resampler<F> {
+        &self.resampler
+    }
+
+    /// Push the next chunk of the signal, returning any output samples
+    /// completed by it
+    pub fn push<T: Num + Bounded + NumCast>(&mut self, samples: &[T]) -> Array1<F> {
+        to_floats(samples, &mut self.scratch);
+        self.buffer.extend_from_slice(&self.scratch);
+        self.received += samples.len();
+
+        let reach = self.resampler.reach();
+        let available = self.resampler.output_len(self.received);
+        let mut output = vec![];
+        while self.next_output < available {
+            let n = self.resampler.input_time(self.next_output) as usize;
+            if n + reach >= self.received {
+                break;
+            }
+            output.push(self.resampler.interpolate(
+                &self.buffer,
+                self.offset,
+                self.received,
+                self.next_output,
+            ));
+            self.next_output += 1;
+        }
+
+        // Drop the samples before the left wing of the next output
+        let n = self.resampler.input_time(self.next_output) as usize;
+        let keep_from = n.saturating_sub(reach).max(self.offset);
+        self.buffer.drain(..keep_from - self.offset);
+        self.offset = keep_from;
+        Array1::from(output)
+    }
+
+    /// Finish the signal, returning the remaining output samples. The stream
+    /// is reset afterwards ready for a new signal
+    pub fn flush(&mut self) -> Array1<F> {
+        let output = (self.next_output..self.resampler.output_len(self.received))
+            .map(|t| {
+                self.resampler
+                    .interpolate(&self.buffer, self.offset, self.received, t)
+            })
+            .collect();
+        self.reset();
+        output
+    }
+
+    /// Discard any buffered samples and start a new signal
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.offset = 0;
+        self.received = 0;
+        self.next_output = 0;
+    }
+}
+
+/// Tabulate the right half of a Kaiser windowed sinc at `2^precision` points
+/// per zero crossing, as `resampy.filters.sinc_window` does
+fn sinc_window(zero_crossings: usize, precision: u32, rolloff: f64, beta: f64) -> Vec<f64> {
+    let num_bits = 1usize << precision;
+    let n = num_bits * zero_crossings;
+    let norm = bessel_i0(beta);
+    (0..=n)
+        .map(|k| {
+            let x = rolloff * k as f64 / num_bits as f64;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let r = k as f64 / n as f64;
+            let taper = bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / norm;
+            rolloff * sinc * taper
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn sine(freq: f64, sample_rate: f64, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|x| (2.0 * PI * freq * x as f64 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn resampled_sines() {
+        let cases = [
+            (16000.0, 8000.0, ResampleFilter::KaiserBest, 1e-4),
+            (8000.0, 44100.0, ResampleFilter::KaiserBest, 1e-4),
+            (48000.0, 22050.0, ResampleFilter::KaiserFast, 1e-2),
+            (22050.0, 16000.0, ResampleFilter::KaiserFast, 1e-2),
+        ];
+        for (orig_sr, target_sr, filter, eps) in cases.iter() {
+            let resampler = ResamplerBuilder::<f64>::with_rates(*orig_sr, *target_sr)
+                .set_filter(*filter)
+                .build();
+            let signal = sine(440.0, *orig_sr as f64, 8000);
+            let result = resampler.run(&signal).unwrap();
+            assert_eq!(result.len(), resampler.output_len(signal.len()));
+            assert_eq!(
+                result.len(),
+                (8000.0 * *target_sr as f64 / *orig_sr as f64) as usize
+            );
+
+            // Away from the edges the sine is reproduced at the new rate
+            let expected = sine(440.0, *target_sr as f64, result.len());
+            let edge = result.len() / 10;
+            for (a, e) in result
+                .iter()
+                .zip(expected.iter())
+                .skip(edge)
+                .take(result.len() - 2 * edge)
+            {
+                assert!(
+                    approx_eq!(f64, *a, *e, epsilon = *eps),
+                    "{} -> {}: {} ~= {}",
+                    orig_sr,
+                    target_sr,
+                    a,
+                    e
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn downsampling_removes_aliases() {
+        // A 6KHz tone is above the nyquist frequency at 8KHz
+        let resampler = ResamplerBuilder::<f64>::with_rates(16000.0, 8000.0).build();
+        let result = resampler.run(&sine(6000.0, 16000.0, 8000)).unwrap();
+        let peak = result
+            .slice(s![400..3600])
+            .fold(0.0f64, |acc, x| acc.max(x.abs()));
+        assert!(peak < 1e-3, "{}", peak);
+    }
+
+    #[test]
+    fn streaming_matches_run() {
+        let signal = sine(440.0, 16000.0, 5000)
+            .iter()
+            .map(|x| *x as f32)
+            .collect::<Vec<_>>();
+        for (orig_sr, target_sr) in [(16000.0, 8000.0), (16000.0, 44100.0), (16000.0, 16000.0)] {
+            let resampler = ResamplerBuilder::new(orig_sr, target_sr)
+                .set_filter(ResampleFilter::KaiserFast)
+                .build();
+            let expected = resampler.run(&signal).unwrap();
+            for chunk_size in [1, 100, 777, 5000] {
+                let mut stream = StreamingResampler::new(resampler.clone());
+                let mut result = vec![];
+                for chunk in signal.chunks(chunk_size) {
+                    result.extend(stream.push(chunk));
+                }
+                if chunk_size == 100 {
+                    // Output is produced before the end of the signal
+                    assert!(!result.is_empty());
+                }
+                result.extend(stream.flush());
+                assert_eq!(Array1::from(result), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn multichannel_resampling() {
+        let resampler = ResamplerBuilder::new(22050.0, 16000.0)
+            .set_filter(ResampleFilter::KaiserFast)
+            .build();
+        let data =
+            Array2::from_shape_fn((2, 3000), |(c, x)| (x as f32 * 0.01 * (c + 1) as f32).sin());
+        let result = resampler.run_multichannel(&data).unwrap();
+        assert_eq!(result.dim(), (2, 2176));
+        for (channel, resampled) in data.outer_iter().zip(result.outer_iter()) {
+            assert_eq!(
+                resampled,
+                resampler.run(channel.as_slice().unwrap()).unwrap()
+            );
+        }
+        // Channels don't need to be contiguous
+        let transposed = data.t().to_owned();
+        let result = resampler.run_multichannel(&transposed.t()).unwrap();
+        assert_eq!(result.dim(), (2, 2176));
+    }
+
+    #[test]
+    fn invalid_resampling() {
+        let resampler = ResamplerBuilder::new(16000.0, 8000.0).build();
+        assert_eq!(
+            resampler.run(&[0.0f32]),
+            Err(Error::SignalTooShort {
+                samples: 1,
+                required: 2
+            })
+        );
+        assert!(ResamplerBuilder::new(0.0, 8000.0).try_build().is_err());
+        assert!(ResamplerBuilder::new(16000.0, 1.0).try_build().is_err());
+        let filter = ResampleFilter::Kaiser {
+            zero_crossings: 0,
+            precision: 9,
+            rolloff: 0.9,
+            beta: 10.0,
+        };
+        assert!(ResamplerBuilder::new(16000.0, 8000.0)
+            .set_filter(filter)
+            .try_build()
+            .is_err());
+    }
+}
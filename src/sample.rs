@@ -0,0 +1,302 @@
+use num_traits::{cast, Bounded, Float, Num};
+use rand::Rng;
+
+/// A packed little-endian 24-bit signed integer sample, as stored in 24-bit
+/// PCM audio
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct I24([u8; 3]);
+
+impl I24 {
+    /// Largest value a 24-bit sample can hold
+    pub const MAX: i32 = (1 << 23) - 1;
+    /// Smallest value a 24-bit sample can hold
+    pub const MIN: i32 = -(1 << 23);
+
+    /// Create a sample from its little-endian bytes
+    pub fn from_le_bytes(bytes: [u8; 3]) -> Self {
+        Self(bytes)
+    }
+
+    /// The little-endian bytes of the sample
+    pub fn to_le_bytes(self) -> [u8; 3] {
+        self.0
+    }
+
+    /// Create a sample from an `i32`, clipping it to the 24-bit range
+    pub fn from_i32(x: i32) -> Self {
+        let bytes = x.clamp(Self::MIN, Self::MAX).to_le_bytes();
+        Self([bytes[0], bytes[1], bytes[2]])
+    }
+
+    /// The sample as a sign extended `i32`
+    pub fn to_i32(self) -> i32 {
+        i32::from_le_bytes([0, self.0[0], self.0[1], self.0[2]]) >> 8
+    }
+}
+
+/// A sample type audio can be stored as. Integer samples represent `[-1, 1)`
+/// scaled by `2^(bits - 1)`, with unsigned samples offset so silence is at the
+/// midpoint. Floating point samples are used as they are
+pub trait Sample: Copy {
+    /// Convert the sample to a float, integers are scaled to `[-1, 1)`
+    fn to_float<F: Float>(self) -> F;
+
+    /// Convert a float to the sample type. For integers the float is scaled by
+    /// `2^(bits - 1)`, rounded and clipped to the range of the type with NaN
+    /// becoming silence
+    fn from_float<F: Float>(x: F) -> Self;
+
+    /// Distance between adjacent quantisation levels on the `[-1, 1)` scale,
+    /// zero for floating point samples
+    fn step() -> f64;
+}
+
+fn int_to_float<F: Float>(x: i32, bits: i32) -> F {
+    cast(f64::from(x) / 2.0f64.powi(bits - 1)).unwrap_or_else(F::zero)
+}
+
+fn float_to_int<F: Float>(x: F, bits: i32) -> i32 {
+    let max = (1i64 << (bits - 1)) - 1;
+    let x = x.to_f64().unwrap_or_default() * (max + 1) as f64;
+    // The cast saturates and maps NaN to 0
+    (x.round() as i64).clamp(-max - 1, max) as i32
+}
+
+macro_rules! impl_int_sample {
+    ($t:ty, $bits:expr) => {
+        impl Sample for $t {
+            fn to_float<F: Float>(self) -> F {
+                int_to_float(i32::from(self), $bits)
+            }
+
+            fn from_float<F: Float>(x: F) -> Self {
+                float_to_int(x, $bits) as $t
+            }
+
+            fn step() -> f64 {
+                2.0f64.powi(1 - $bits)
+            }
+        }
+    };
+}
+
+impl_int_sample!(i8, 8);
+impl_int_sample!(i16, 16);
+impl_int_sample!(i32, 32);
+
+impl Sample for u8 {
+    fn to_float<F: Float>(self) -> F {
+        int_to_float(i32::from(self) - 128, 8)
+    }
+
+    fn from_float<F: Float>(x: F) -> Self {
+        (float_to_int(x, 8) + 128) as u8
+    }
+
+    fn step() -> f64 {
+        2.0f64.powi(-7)
+    }
+}
+
+impl Sample for I24 {
+    fn to_float<F: Float>(self) -> F {
+        int_to_float(self.to_i32(), 24)
+    }
+
+    fn from_float<F: Float>(x: F) -> Self {
+        Self::from_i32(float_to_int(x, 24))
+    }
+
+    fn step() -> f64 {
+        2.0f64.powi(-23)
+    }
+}
+
+macro_rules! impl_float_sample {
+    ($t:ty) => {
+        impl Sample for $t {
+            fn to_float<F: Float>(self) -> F {
+                cast(self).unwrap_or_else(F::nan)
+            }
+
+            fn from_float<F: Float>(x: F) -> Self {
+                cast(x).unwrap_or(<$t>::NAN)
+            }
+
+            fn step() -> f64 {
+                0.0
+            }
+        }
+    };
+}
+
+impl_float_sample!(f32);
+impl_float_sample!(f64);
+
+/// Dither added to floats before they're quantised to integer samples
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Round to the nearest quantisation level
+    #[default]
+    None,
+    /// Add triangular probability density noise spanning one quantisation step
+    /// either side of the sample, decorrelating the quantisation error from
+    /// the signal
+    Triangular,
+}
+
+/// Convert samples to floats, scaling integer samples to `[-1, 1)`
+pub fn samples_to_float<T: Sample, F: Float>(samples: &[T]) -> Vec<F> {
+    samples.iter().map(|x| x.to_float()).collect()
+}
+
+/// Convert floats to samples, scaling by `2^(bits - 1)` and clipping for
+/// integer samples. Dither is ignored when converting to floats
+pub fn float_to_samples<T: Sample, F: Float>(samples: &[F], dither: Dither) -> Vec<T> {
+    float_to_samples_with_rng(samples, dither, &mut rand::thread_rng())
+}
+
+/// Convert floats to samples as `float_to_samples` does, drawing any dither
+/// from the provided random number generator
+pub fn float_to_samples_with_rng<T, F, R>(samples: &[F], dither: Dither, rng: &mut R) -> Vec<T>
+where
+    T: Sample,
+    F: Float,
+    R: Rng,
+{
+    let step = T::step();
+    if dither == Dither::None || step == 0.0 {
+        return samples.iter().map(|x| T::from_float(*x)).collect();
+    }
+    samples
+        .iter()
+        .map(|x| {
+            let noise = (rng.gen::<f64>() - rng.gen::<f64>()) * step;
+            T::from_float(x.to_f64().unwrap_or(f64::NAN) + noise)
+        })
+        .collect()
+}
+
+/// The offset and scale mapping an integer type onto `[-1, 1)`, or `None` if
+/// the type isn't an integer. Integers are detected by `1 / 2` rounding to zero
+/// and the scale is half the number of values the type can represent
+pub(crate) fn integer_normalisation<T: Num + Bounded + num_traits::NumCast>() -> Option<(f64, f64)>
+{
+    let two = T::one() + T::one();
+    if !(T::one() / two).is_zero() {
+        return None;
+    }
+    let min = T::min_value().to_f64()?;
+    let max = T::max_value().to_f64()?;
+    Some(((max + min + 1.0) / 2.0, (max - min + 1.0) / 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+    use rand::SeedableRng;
+
+    #[test]
+    fn integer_scaling() {
+        assert_eq!(
+            samples_to_float::<i16, f32>(&[i16::MIN, 0, 16384, i16::MAX]),
+            vec![-1.0, 0.0, 0.5, 32767.0 / 32768.0]
+        );
+        assert_eq!(
+            samples_to_float::<u8, f64>(&[0, 128, 192, 255]),
+            vec![-1.0, 0.0, 0.5, 127.0 / 128.0]
+        );
+        assert_eq!(
+            samples_to_float::<i8, f32>(&[-128, -64, 127]),
+            vec![-1.0, -0.5, 127.0 / 128.0]
+        );
+        assert_eq!(
+            samples_to_float::<i32, f64>(&[i32::MIN, 1 << 30]),
+            vec![-1.0, 0.5]
+        );
+        let packed = [
+            I24::from_le_bytes([0, 0, 0x80]),
+            I24::from_i32(1 << 22),
+            I24::from_i32(-1),
+        ];
+        assert_eq!(
+            samples_to_float::<I24, f64>(&packed),
+            vec![-1.0, 0.5, -1.0 / 8388608.0]
+        );
+        assert_eq!(packed[2].to_le_bytes(), [0xff, 0xff, 0xff]);
+        assert_eq!(I24::from_i32(1 << 24).to_i32(), I24::MAX);
+        assert_eq!(I24::from_i32(i32::MIN).to_i32(), I24::MIN);
+    }
+
+    #[test]
+    fn quantisation() {
+        let signal = [0.0f32, 0.5, -0.5, 1.0, -1.0, 2.0, -2.0, f32::NAN];
+        assert_eq!(
+            float_to_samples::<i16, _>(&signal, Dither::None),
+            vec![0, 16384, -16384, 32767, -32768, 32767, -32768, 0]
+        );
+        assert_eq!(
+            float_to_samples::<u8, _>(&signal, Dither::None),
+            vec![128, 192, 64, 255, 0, 255, 0, 128]
+        );
+        assert_eq!(
+            float_to_samples::<i8, _>(&signal, Dither::None),
+            vec![0, 64, -64, 127, -128, 127, -128, 0]
+        );
+        assert_eq!(
+            float_to_samples::<i32, _>(&signal[..5], Dither::None),
+            vec![0, 1 << 30, -(1 << 30), i32::MAX, i32::MIN]
+        );
+        let packed = float_to_samples::<I24, _>(&signal[..5], Dither::None);
+        let packed = packed.iter().map(|x| x.to_i32()).collect::<Vec<_>>();
+        assert_eq!(packed, vec![0, 1 << 22, -(1 << 22), I24::MAX, I24::MIN]);
+        // Dither has no effect converting between floats
+        assert_eq!(
+            float_to_samples::<f64, _>(&signal[..7], Dither::Triangular),
+            signal[..7]
+                .iter()
+                .map(|x| f64::from(*x))
+                .collect::<Vec<_>>()
+        );
+
+        let signal = (0..1000)
+            .map(|x| (x as f32 * 0.01).sin() * 0.9)
+            .collect::<Vec<_>>();
+        let quantised = float_to_samples::<i16, _>(&signal, Dither::None);
+        let recon = samples_to_float::<i16, f32>(&quantised);
+        for (a, e) in recon.iter().zip(signal.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = 0.5 / 32768.0),
+                "{} ~= {}",
+                a,
+                e
+            );
+        }
+    }
+
+    #[test]
+    fn triangular_dither() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        // A quarter of a step between levels is always rounded down without
+        // dither, with TPDF dither it averages out and never moves more than
+        // a step away
+        let signal = vec![0.25 / 128.0; 20000];
+        let dithered =
+            float_to_samples_with_rng::<i8, f64, _>(&signal, Dither::Triangular, &mut rng);
+        assert!(dithered.iter().all(|x| (-1..=1).contains(x)));
+        let mean = dithered.iter().map(|x| f64::from(*x)).sum::<f64>() / signal.len() as f64;
+        assert!(approx_eq!(f64, mean, 0.25, epsilon = 0.02), "{}", mean);
+        assert!(float_to_samples::<i8, _>(&signal, Dither::None)
+            .iter()
+            .all(|x| *x == 0));
+    }
+
+    #[test]
+    fn detect_integers() {
+        assert_eq!(integer_normalisation::<i16>(), Some((0.0, 32768.0)));
+        assert_eq!(integer_normalisation::<u8>(), Some((128.0, 128.0)));
+        assert_eq!(integer_normalisation::<f32>(), None);
+        assert_eq!(integer_normalisation::<f64>(), None);
+    }
+}
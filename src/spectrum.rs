@@ -1,70 +1,582 @@
-use crate::stft::ShortTimeFourierTransform;
-use ndarray::{prelude::*, Array2, Data};
+use crate::decibel::{power_to_db, DbReference};
+use crate::error::Error;
+use crate::filterbank::MelFilterbank;
+use crate::stft::{ShortTimeFourierTransform, StftFloat};
+use ndarray::{prelude::*, Data};
 use num_traits::{Bounded, Num, NumCast};
 use rustfft::num_complex::Complex;
-use std::f32::{consts::PI, NAN};
 
 /// Get the different frequency components from a type containing frequency data
-pub trait FrequencyComponents {
-    /// Gets the magnitude data as a 2D plot
-    fn mag(&self) -> Array2<f32>;
-    /// Gets the phase data as a 2D plot
-    fn phase(&self) -> Array2<f32>;
+pub trait FrequencyComponents<F = f32, D = Ix2> {
+    /// Gets the magnitude data as a 2D plot, or one plot per channel for
+    /// multichannel spectra
+    fn mag(&self) -> Array<F, D>;
+    /// Gets the phase data as a 2D plot, or one plot per channel for
+    /// multichannel spectra
+    fn phase(&self) -> Array<F, D>;
 }
 
-impl<T> FrequencyComponents for ArrayBase<T, Ix2>
+impl<T, F, D> FrequencyComponents<F, D> for ArrayBase<T, D>
 where
-    T: Data<Elem = Complex<f32>>,
+    T: Data<Elem = Complex<F>>,
+    F: StftFloat,
+    D: Dimension,
 {
     /// Convert the complex array into it's magnitude spectra
-    fn mag(&self) -> Array2<f32> {
+    fn mag(&self) -> Array<F, D> {
         self.mapv(|x| x.norm())
     }
 
     /// Convert every complex number into it's phase. Result may contain NAN
     /// values for instances where `Im(x) == 0 && Re(x) == 0`
-    fn phase(&self) -> Array2<f32> {
+    fn phase(&self) -> Array<F, D> {
         self.mapv(|x| {
-            if x.re > 0.0 || x.im.abs() > std::f32::EPSILON {
-                2.0 * (x.im / (x.norm() + x.re)).atan()
-            } else if x.re < 0.0 && x.im.abs() <= std::f32::EPSILON {
-                PI
+            if x.re > F::zero() || x.im.abs() > F::epsilon() {
+                (x.im / (x.norm() + x.re)).atan() * 2.0f32.into()
+            } else if x.re < F::zero() && x.im.abs() <= F::epsilon() {
+                F::PI()
             } else {
-                NAN
+                F::nan()
             }
         })
     }
 }
 
-/// Gets a spectrogram from an audio signal
-pub trait SpectrumExt {
+/// Gets a spectrogram from an audio signal. The spectrogram is computed in the
+/// precision of the `ShortTimeFourierTransform`. Multichannel signals with the
+/// samples along the last axis, such as `(channels, samples)` or
+/// `(batch, channels, samples)`, give one spectrogram per channel with shape
+/// `(channels, freq, frames)` or `(batch, channels, freq, frames)`
+pub trait SpectrumExt<F = f32> {
     type Output;
 
     /// Given an stft object create a spectrogram with the given power and
     /// stft parameters. If no power is provided 1.0 is used as default
-    fn spectrum(&self, stft: ShortTimeFourierTransform, power: Option<f32>) -> Self::Output;
+    fn spectrum(&self, stft: &ShortTimeFourierTransform<F>, power: Option<F>) -> Self::Output;
+
+    /// Create a mel spectrogram by applying the mel filterbank to the
+    /// spectrogram with the given power. If no power is provided 2.0 is used
+    /// as default in line with librosa
+    fn melspectrogram(
+        &self,
+        stft: &ShortTimeFourierTransform<F>,
+        filterbank: &MelFilterbank,
+        power: Option<F>,
+    ) -> Self::Output;
 }
 
-impl<T, U> SpectrumExt for ArrayBase<T, Ix1>
+impl<T, U, F, D> SpectrumExt<F> for ArrayBase<T, D>
 where
     T: Data<Elem = U>,
     U: Num + Bounded + NumCast,
+    F: StftFloat,
+    D: Dimension,
+{
+    type Output = Result<Array<F, D::Larger>, Error>;
+
+    fn spectrum(&self, stft: &ShortTimeFourierTransform<F>, power: Option<F>) -> Self::Output {
+        let power = power.unwrap_or_else(F::one);
+        let mut mag = stft.run_multichannel(self)?.mag();
+        mag.mapv_inplace(|x| x.powf(power));
+        Ok(mag)
+    }
+
+    fn melspectrogram(
+        &self,
+        stft: &ShortTimeFourierTransform<F>,
+        filterbank: &MelFilterbank,
+        power: Option<F>,
+    ) -> Self::Output {
+        if filterbank.n_fft() != stft.n_fft() {
+            return Err(Error::InvalidParameter(format!(
+                "filterbank designed for {} FFT points but the STFT uses {}",
+                filterbank.n_fft(),
+                stft.n_fft()
+            )));
+        }
+        let spectra = self.spectrum(stft, Some(power.unwrap_or_else(|| 2.0f32.into())))?;
+        apply_filterbank(filterbank, spectra)
+    }
+}
+
+/// Apply the filterbank to every channel of a spectrogram with shape
+/// `(..., freq, frames)`
+fn apply_filterbank<F, D>(
+    filterbank: &MelFilterbank,
+    spectra: Array<F, D>,
+) -> Result<Array<F, D>, Error>
+where
+    F: StftFloat,
+    D: Dimension,
+{
+    let ndim = spectra.ndim();
+    let mut shape = spectra.shape().to_vec();
+    let (bins, frames) = (shape[ndim - 2], shape[ndim - 1]);
+    let channels = shape[..ndim - 2].iter().product();
+    let spectra = spectra
+        .into_shape((channels, bins, frames))
+        .map_err(|_| Error::NonContiguous)?;
+
+    let mut result = Array3::zeros((channels, filterbank.n_mels(), frames));
+    for (spectrogram, mut mel) in spectra.outer_iter().zip(result.outer_iter_mut()) {
+        mel.assign(&filterbank.apply(&spectrogram)?);
+    }
+    shape[ndim - 2] = filterbank.n_mels();
+    Ok(result
+        .into_shape(shape)
+        .and_then(|x| x.into_dimensionality())
+        .expect("Mel spectrogram has the same axes as the spectrogram"))
+}
+
+/// Get the centre frequency of each FFT bin for a spectrogram of `1 + n_fft/2`
+/// bins, as `librosa.fft_frequencies` does. Bin `k` is at `k * sample_rate /
+/// n_fft` so for odd `n_fft` the last bin is below the nyquist frequency
+pub fn fft_frequencies<F: StftFloat>(sample_rate: F, n_fft: usize) -> Array1<F> {
+    let n = F::from_usize(n_fft).unwrap();
+    Array1::from_shape_fn(1 + n_fft / 2, |k| {
+        F::from_usize(k).unwrap() * sample_rate / n
+    })
+}
+
+/// Per-frame spectral features of a magnitude spectrogram with shape
+/// `(1 + n_fft/2, frames)`, such as the output of `FrequencyComponents::mag`.
+/// The features match those in `librosa.feature` and the frequency of each
+/// bin is taken from `fft_frequencies`. Features using the bin frequencies
+/// take the FFT size and return an error if the spectrogram doesn't have
+/// `1 + n_fft/2` bins
+pub trait SpectralFeaturesExt<F = f32> {
+    /// The weighted mean frequency of each frame. Silent frames have a
+    /// centroid of zero
+    fn spectral_centroid(&self, sample_rate: F, n_fft: usize) -> Result<Array1<F>, Error>;
+
+    /// The `p`-order deviation of the frequencies about the centroid, weighted
+    /// by the normalised spectrum. librosa uses `p = 2`
+    fn spectral_bandwidth(&self, sample_rate: F, n_fft: usize, p: F) -> Result<Array1<F>, Error>;
+
+    /// The lowest frequency with at least `roll_percent` of the energy of the
+    /// frame at or below it. `roll_percent` must be between 0 and 1 and
+    /// librosa uses 0.85
+    fn spectral_rolloff(
+        &self,
+        sample_rate: F,
+        n_fft: usize,
+        roll_percent: F,
+    ) -> Result<Array1<F>, Error>;
+
+    /// The ratio of the geometric to the arithmetic mean of the spectrum raised
+    /// to `power`, with values clipped below at `amin`. This is 1.0 for white
+    /// noise and close to 0.0 for pure tones. librosa uses a power of 2.0 and
+    /// an `amin` of `1e-10`
+    fn spectral_flatness(&self, power: F, amin: F) -> Result<Array1<F>, Error>;
+
+    /// The difference between the peaks and valleys of the spectrum in
+    /// `n_bands` octave bands starting at `fmin`, plus a band below `fmin`,
+    /// giving an array of shape `(n_bands + 1, frames)`. The peak and valley
+    /// are the means of the top and bottom `quantile` of each band and the
+    /// difference is in decibels unless `linear` is set. librosa uses an
+    /// `fmin` of 200Hz, 6 bands and a quantile of 0.02
+    fn spectral_contrast(
+        &self,
+        sample_rate: F,
+        n_fft: usize,
+        fmin: F,
+        n_bands: usize,
+        quantile: F,
+        linear: bool,
+    ) -> Result<Array2<F>, Error>;
+
+    /// The euclidean distance between the spectra of each frame and the frame
+    /// before it. The first frame has no predecessor so has a flux of zero
+    fn spectral_flux(&self) -> Array1<F>;
+}
+
+impl<S, F> SpectralFeaturesExt<F> for ArrayBase<S, Ix2>
+where
+    S: Data<Elem = F>,
+    F: StftFloat,
 {
-    type Output = Option<Array<f32, Ix2>>;
-
-    fn spectrum(&self, stft: ShortTimeFourierTransform, power: Option<f32>) -> Self::Output {
-        let power = power.unwrap_or(1.0);
-        if let Some(data) = self.as_slice() {
-            stft.run(data).map(|r| {
-                let mut mag = r.mag();
-                mag.mapv_inplace(|x| x.powf(power));
-                mag
+    fn spectral_centroid(&self, sample_rate: F, n_fft: usize) -> Result<Array1<F>, Error> {
+        check_non_negative(self)?;
+        let freq = bin_frequencies(self, sample_rate, n_fft)?;
+        Ok(normalise_frames(self).t().dot(&freq))
+    }
+
+    fn spectral_bandwidth(&self, sample_rate: F, n_fft: usize, p: F) -> Result<Array1<F>, Error> {
+        let centroid = self.spectral_centroid(sample_rate, n_fft)?;
+        let freq = bin_frequencies(self, sample_rate, n_fft)?;
+        let norm = normalise_frames(self);
+        Ok(Array1::from_shape_fn(self.ncols(), |t| {
+            let sum = norm
+                .column(t)
+                .iter()
+                .zip(freq.iter())
+                .fold(F::zero(), |acc, (s, f)| {
+                    acc + *s * (*f - centroid[t]).abs().powf(p)
+                });
+            sum.powf(p.recip())
+        }))
+    }
+
+    fn spectral_rolloff(
+        &self,
+        sample_rate: F,
+        n_fft: usize,
+        roll_percent: F,
+    ) -> Result<Array1<F>, Error> {
+        if roll_percent <= F::zero() || roll_percent >= F::one() {
+            return Err(Error::InvalidParameter(
+                "roll_percent must lie in the range (0, 1)".to_string(),
+            ));
+        }
+        check_non_negative(self)?;
+        let freq = bin_frequencies(self, sample_rate, n_fft)?;
+        Ok(self
+            .axis_iter(Axis(1))
+            .map(|frame| {
+                let threshold = roll_percent * frame.sum();
+                let mut total = F::zero();
+                for (s, f) in frame.iter().zip(freq.iter()) {
+                    total = total + *s;
+                    if total >= threshold {
+                        return *f;
+                    }
+                }
+                F::nan()
             })
+            .collect())
+    }
+
+    fn spectral_flatness(&self, power: F, amin: F) -> Result<Array1<F>, Error> {
+        if amin <= F::zero() {
+            return Err(Error::InvalidParameter("amin must be positive".to_string()));
+        }
+        check_non_negative(self)?;
+        let bins = F::from_usize(self.nrows()).unwrap();
+        Ok(self
+            .axis_iter(Axis(1))
+            .map(|frame| {
+                let thresh = frame.mapv(|x| x.powf(power).max(amin));
+                let gmean = (thresh.mapv(F::ln).sum() / bins).exp();
+                let amean = thresh.sum() / bins;
+                gmean / amean
+            })
+            .collect())
+    }
+
+    fn spectral_contrast(
+        &self,
+        sample_rate: F,
+        n_fft: usize,
+        fmin: F,
+        n_bands: usize,
+        quantile: F,
+        linear: bool,
+    ) -> Result<Array2<F>, Error> {
+        if fmin <= F::zero() {
+            return Err(Error::InvalidParameter("fmin must be positive".to_string()));
+        }
+        if n_bands == 0 {
+            return Err(Error::InvalidParameter(
+                "n_bands must be at least 1".to_string(),
+            ));
+        }
+        if quantile <= F::zero() || quantile >= F::one() {
+            return Err(Error::InvalidParameter(
+                "quantile must lie in the range (0, 1)".to_string(),
+            ));
+        }
+        let nyquist = sample_rate / 2.0f32.into();
+        let mut octaves = vec![F::zero()];
+        octaves.extend((0..=n_bands).map(|k| fmin * F::from_u64(1 << k).unwrap()));
+        if octaves[..=n_bands].iter().any(|f| *f >= nyquist) {
+            return Err(Error::InvalidParameter(
+                "frequency band exceeds the nyquist frequency, reduce fmin or n_bands".to_string(),
+            ));
+        }
+
+        let freq = bin_frequencies(self, sample_rate, n_fft)?;
+        let mut valley = Array2::zeros((n_bands + 1, self.ncols()));
+        let mut peak = Array2::zeros((n_bands + 1, self.ncols()));
+        for k in 0..=n_bands {
+            let (low, high) = (octaves[k], octaves[k + 1]);
+            let in_band = |f: &F| *f >= low && *f <= high;
+            let first = freq.iter().position(in_band);
+            let last = freq.iter().rposition(in_band);
+            let (first, last) = match (first, last) {
+                (Some(first), Some(last)) => (first, last),
+                _ => {
+                    return Err(Error::InvalidParameter(format!(
+                        "no frequency bins between {:?}Hz and {:?}Hz",
+                        low.to_f64(),
+                        high.to_f64()
+                    )))
+                }
+            };
+            // Each band shares its lowest bin with the band below and the last
+            // band takes everything above it
+            let start = if k > 0 { first - 1 } else { first };
+            let end = if k == n_bands { self.nrows() } else { last + 1 };
+            let band_end = if k < n_bands { end - 1 } else { end };
+            let count = F::from_usize(end - start).unwrap();
+            let n = round_half_even(quantile * count)
+                .to_usize()
+                .unwrap_or(0)
+                .max(1);
+
+            let mut sorted = vec![];
+            for (t, frame) in self.axis_iter(Axis(1)).enumerate() {
+                sorted.clear();
+                sorted.extend(frame.slice(s![start..band_end]).iter().cloned());
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                let n = n.min(sorted.len());
+                let mean = |x: &[F]| {
+                    x.iter().fold(F::zero(), |acc, x| acc + *x) / F::from_usize(n).unwrap()
+                };
+                valley[[k, t]] = mean(&sorted[..n]);
+                peak[[k, t]] = mean(&sorted[sorted.len() - n..]);
+            }
+        }
+
+        if linear {
+            Ok(peak - valley)
         } else {
-            None
+            let reference = DbReference::Value(F::one());
+            let amin = F::from_f64(1e-10).unwrap();
+            let top_db = Some(F::from_f64(80.0).unwrap());
+            Ok(power_to_db(&peak, reference, amin, top_db)?
+                - power_to_db(&valley, reference, amin, top_db)?)
+        }
+    }
+
+    fn spectral_flux(&self) -> Array1<F> {
+        let mut flux = Array1::zeros(self.ncols());
+        for t in 1..self.ncols() {
+            flux[t] = self
+                .column(t)
+                .iter()
+                .zip(self.column(t - 1).iter())
+                .fold(F::zero(), |acc, (a, b)| acc + (*a - *b) * (*a - *b))
+                .sqrt();
         }
+        flux
+    }
+}
+
+/// Frequencies of the rows of a spectrogram, checking it has the `1 + n_fft/2`
+/// bins of an FFT of `n_fft` points
+fn bin_frequencies<S, F>(
+    spectrogram: &ArrayBase<S, Ix2>,
+    sample_rate: F,
+    n_fft: usize,
+) -> Result<Array1<F>, Error>
+where
+    S: Data<Elem = F>,
+    F: StftFloat,
+{
+    if spectrogram.nrows() != 1 + n_fft / 2 {
+        return Err(Error::InvalidParameter(format!(
+            "spectrogram has {} frequency bins but an FFT of {} points has {}",
+            spectrogram.nrows(),
+            n_fft,
+            1 + n_fft / 2
+        )));
+    }
+    Ok(fft_frequencies(sample_rate, n_fft))
+}
+
+/// Normalise each frame to sum to one, leaving silent frames as they are like
+/// `librosa.util.normalize`
+fn normalise_frames<S, F>(spectrogram: &ArrayBase<S, Ix2>) -> Array2<F>
+where
+    S: Data<Elem = F>,
+    F: StftFloat,
+{
+    let mut norm = spectrogram.to_owned();
+    for mut frame in norm.axis_iter_mut(Axis(1)) {
+        let total = frame.fold(F::zero(), |acc, x| acc + x.abs());
+        if total >= F::min_positive_value() {
+            frame.mapv_inplace(|x| x / total);
+        }
+    }
+    norm
+}
+
+fn check_non_negative<S, F>(spectrogram: &ArrayBase<S, Ix2>) -> Result<(), Error>
+where
+    S: Data<Elem = F>,
+    F: StftFloat,
+{
+    if spectrogram.iter().any(|x| *x < F::zero()) {
+        Err(Error::InvalidParameter(
+            "spectral features are only defined for non-negative spectra".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Round to the nearest integer with halves going to the even integer, as
+/// `numpy.rint` does
+fn round_half_even<F: StftFloat>(x: F) -> F {
+    let rounded = x.round();
+    let two: F = 2.0f32.into();
+    if (rounded - x).abs() == 0.5f32.into() && rounded % two != F::zero() {
+        rounded - x.signum()
+    } else {
+        rounded
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::filterbank::MelFilterbankBuilder;
+    use crate::stft::StftBuilder;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn melspectrogram_shape() {
+        let data = Array1::from(
+            (0..4000)
+                .map(|x| (x as f32 * 0.1).sin())
+                .collect::<Vec<_>>(),
+        );
+        let stft = StftBuilder::new().set_fft_num(512).set_hop_len(160).build();
+        let filterbank = MelFilterbankBuilder::new(16000.0, 512)
+            .set_mel_num(40)
+            .build();
+
+        let mel = data.melspectrogram(&stft, &filterbank, None).unwrap();
+        let spectra = data.spectrum(&stft, Some(2.0)).unwrap();
+        assert_eq!(mel.dim(), (40, spectra.ncols()));
+        assert_eq!(mel, filterbank.weights().dot(&spectra));
+
+        let mismatched = MelFilterbankBuilder::new(16000.0, 1024).build();
+        assert!(matches!(
+            data.melspectrogram(&stft, &mismatched, None),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let strided = data.slice(s![..;2]);
+        assert_eq!(strided.spectrum(&stft, None), Err(Error::NonContiguous));
+    }
+
+    #[test]
+    fn multichannel_spectra() {
+        let data =
+            Array2::from_shape_fn((3, 4000), |(c, x)| (x as f32 * 0.01 * (c + 1) as f32).sin());
+        let stft = StftBuilder::new().set_fft_num(512).set_hop_len(160).build();
+        let filterbank = MelFilterbankBuilder::new(16000.0, 512)
+            .set_mel_num(40)
+            .build();
+
+        let spectra = data.spectrum(&stft, None).unwrap();
+        let mel = data.melspectrogram(&stft, &filterbank, None).unwrap();
+        assert_eq!(spectra.dim(), (3, 257, 26));
+        assert_eq!(mel.dim(), (3, 40, 26));
+        for (c, channel) in data.outer_iter().enumerate() {
+            assert_eq!(
+                spectra.index_axis(Axis(0), c),
+                channel.spectrum(&stft, None).unwrap()
+            );
+            assert_eq!(
+                mel.index_axis(Axis(0), c),
+                channel.melspectrogram(&stft, &filterbank, None).unwrap()
+            );
+        }
+
+        let batch = data.clone().into_shape((1, 3, 4000)).unwrap();
+        let batch_mel = batch.melspectrogram(&stft, &filterbank, None).unwrap();
+        assert_eq!(batch_mel.index_axis(Axis(0), 0), mel);
+    }
+
+    #[test]
+    fn spectral_features() {
+        // 8 bins from 0Hz to 3500Hz at an 7000Hz sample rate
+        let sr = 7000.0;
+        let mut spectrogram = Array2::<f64>::zeros((8, 4));
+        spectrogram[[2, 1]] = 3.0;
+        spectrogram.column_mut(2).fill(1.0);
+        spectrogram[[1, 3]] = 1.0;
+        spectrogram[[5, 3]] = 1.0;
+        assert_eq!(
+            fft_frequencies(sr, 14),
+            bin_frequencies(&spectrogram, sr, 14).unwrap()
+        );
+
+        let centroid = spectrogram.spectral_centroid(sr, 14).unwrap();
+        assert_eq!(centroid, array![0.0, 1000.0, 1750.0, 1500.0]);
+
+        // The flat frame has a variance of 1312500Hz^2 about its centroid
+        let bandwidth = spectrogram.spectral_bandwidth(sr, 14, 2.0).unwrap();
+        let expected = [0.0, 0.0, 1_312_500.0f64.sqrt(), 1000.0];
+        for (a, e) in bandwidth.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+
+        let rolloff = spectrogram.spectral_rolloff(sr, 14, 0.85).unwrap();
+        assert_eq!(rolloff, array![0.0, 1000.0, 3000.0, 2500.0]);
+        let rolloff = spectrogram.spectral_rolloff(sr, 14, 0.5).unwrap();
+        assert_eq!(rolloff, array![0.0, 1000.0, 1500.0, 500.0]);
+        assert!(spectrogram.spectral_rolloff(sr, 14, 1.0).is_err());
+
+        let flatness = spectrogram.spectral_flatness(2.0, 1e-10).unwrap();
+        assert!(approx_eq!(f64, flatness[0], 1.0, epsilon = 1e-9));
+        assert!(approx_eq!(f64, flatness[2], 1.0, epsilon = 1e-9));
+        assert!(flatness[1] < 1e-8);
+        assert!(spectrogram.spectral_flatness(2.0, 0.0).is_err());
+
+        let flux = spectrogram.spectral_flux();
+        let expected = [0.0, 3.0, 11.0f64.sqrt(), 6.0f64.sqrt()];
+        for (a, e) in flux.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-9), "{} ~= {}", a, e);
+        }
+
+        assert!(spectrogram.mapv(|x| -x).spectral_centroid(sr, 14).is_err());
+
+        // An odd FFT has the same number of bins with the last one below the
+        // nyquist frequency, as numpy.fft.rfftfreq(15, 1 / 7000) gives
+        let odd = fft_frequencies(sr, 15);
+        assert_eq!(odd.len(), 8);
+        for (k, f) in odd.iter().enumerate() {
+            assert!(approx_eq!(f64, *f, k as f64 * 7000.0 / 15.0, ulps = 2));
+        }
+        let centroid = spectrogram.spectral_centroid(sr, 15).unwrap();
+        assert!(approx_eq!(f64, centroid[1], 2.0 * 7000.0 / 15.0, ulps = 2));
+        assert!(matches!(
+            spectrogram.spectral_centroid(sr, 16),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(spectrogram.spectral_rolloff(sr, 12, 0.85).is_err());
+    }
+
+    #[test]
+    fn spectral_contrast() {
+        let data = Array1::from(
+            (0..4000)
+                .map(|x| (x as f32 * 0.1).sin() + 0.1 * (x as f32 * 1.3).cos())
+                .collect::<Vec<_>>(),
+        );
+        let stft = StftBuilder::new().set_fft_num(512).set_hop_len(160).build();
+        let spectra = data.spectrum(&stft, None).unwrap();
+
+        let contrast = spectra
+            .spectral_contrast(16000.0, 512, 200.0, 6, 0.02, false)
+            .unwrap();
+        assert_eq!(contrast.dim(), (7, spectra.ncols()));
+        assert!(contrast.iter().all(|x| *x >= 0.0));
+
+        let linear = spectra
+            .spectral_contrast(16000.0, 512, 200.0, 6, 0.02, true)
+            .unwrap();
+        assert!(linear.iter().all(|x| *x >= 0.0));
+
+        // The top band would start above the nyquist frequency
+        assert!(spectra
+            .spectral_contrast(8000.0, 512, 200.0, 6, 0.02, false)
+            .is_err());
+        assert!(spectra
+            .spectral_contrast(16000.0, 512, 200.0, 6, 0.0, false)
+            .is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use crate::error::Error;
+use crate::filterbank::{MelFilterbank, MelScale};
+use crate::frame::{frame, power_spectrum};
+use crate::preemphasis::PreemphasisExt;
+use crate::window::{WindowMode, WindowingAlgorithm};
+use ndarray::prelude::*;
+use num_traits::{Bounded, Num, NumCast};
+
+/// Builds a SpeechFeatures instance. Any parameters not set take the defaults
+/// of python_speech_features
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeechFeaturesBuilder {
+    sample_rate: f32,
+    winlen: Option<f32>,
+    winstep: Option<f32>,
+    nfilt: Option<usize>,
+    nfft: Option<usize>,
+    lowfreq: Option<f32>,
+    highfreq: Option<f32>,
+    preemph: Option<f32>,
+    winfunc: Option<WindowingAlgorithm>,
+}
+
+impl SpeechFeaturesBuilder {
+    /// Create a builder for a signal with the given sample rate
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            winlen: None,
+            winstep: None,
+            nfilt: None,
+            nfft: None,
+            lowfreq: None,
+            highfreq: None,
+            preemph: None,
+            winfunc: None,
+        }
+    }
+
+    /// Set the length of each frame in seconds
+    pub fn set_winlen(mut self, seconds: f32) -> Self {
+        self.winlen = Some(seconds);
+        self
+    }
+
+    /// Set the time between the starts of successive frames in seconds
+    pub fn set_winstep(mut self, seconds: f32) -> Self {
+        self.winstep = Some(seconds);
+        self
+    }
+
+    /// Set the number of filters in the filterbank
+    pub fn set_filter_num(mut self, n: usize) -> Self {
+        self.nfilt = Some(n);
+        self
+    }
+
+    /// Set the number of FFT bins. Frames longer than this are truncated
+    pub fn set_fft_num(mut self, n: usize) -> Self {
+        self.nfft = Some(n);
+        self
+    }
+
+    /// Set the lowest frequency of the filters in Hz
+    pub fn set_lowfreq(mut self, freq: f32) -> Self {
+        self.lowfreq = Some(freq);
+        self
+    }
+
+    /// Set the highest frequency of the filters in Hz
+    pub fn set_highfreq(mut self, freq: f32) -> Self {
+        self.highfreq = Some(freq);
+        self
+    }
+
+    /// Set the preemphasis coefficient, 0 disables preemphasis
+    pub fn set_preemphasis(mut self, coefficient: f32) -> Self {
+        self.preemph = Some(coefficient);
+        self
+    }
+
+    /// Set the window applied to each frame. python_speech_features takes a
+    /// function of the frame length, which for the numpy windows is the
+    /// symmetric variant
+    pub fn set_window(mut self, window: WindowingAlgorithm) -> Self {
+        self.winfunc = Some(window);
+        self
+    }
+
+    /// Build the SpeechFeatures instance. The defaults are
+    ///
+    /// winlen default 25ms
+    /// winstep default 10ms
+    /// nfilt default 26
+    /// nfft default 512
+    /// lowfreq default 0Hz
+    /// highfreq default half the sample rate
+    /// preemph default 0.97
+    /// winfunc default rectangular
+    pub fn build(self) -> SpeechFeatures {
+        let nfilt = self.nfilt.unwrap_or(26);
+        let nfft = self.nfft.unwrap_or(512);
+        let lowfreq = self.lowfreq.unwrap_or(0.0);
+        let highfreq = self.highfreq.unwrap_or(self.sample_rate / 2.0);
+        let frame_len = round_half_up(self.winlen.unwrap_or(0.025) * self.sample_rate);
+        let frame_step = round_half_up(self.winstep.unwrap_or(0.01) * self.sample_rate);
+        let window = self
+            .winfunc
+            .unwrap_or(WindowingAlgorithm::Rectangular)
+            .generate(frame_len, WindowMode::Symmetric);
+        let filterbank = MelFilterbank::from_weights(
+            self.sample_rate,
+            nfft,
+            filterbank(nfilt, nfft, self.sample_rate, lowfreq, highfreq),
+        );
+        SpeechFeatures {
+            frame_len,
+            frame_step,
+            window,
+            filterbank,
+            preemph: self.preemph.unwrap_or(0.97),
+        }
+    }
+}
+
+/// Computes the filterbank based features of python_speech_features. Build
+/// using the SpeechFeaturesBuilder
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeechFeatures {
+    frame_len: usize,
+    frame_step: usize,
+    window: Array1<f32>,
+    filterbank: MelFilterbank,
+    preemph: f32,
+}
+
+impl SpeechFeatures {
+    /// The length of each frame in samples
+    pub fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+
+    /// The number of samples between the starts of successive frames
+    pub fn frame_step(&self) -> usize {
+        self.frame_step
+    }
+
+    /// The filterbank with shape `(nfilt, 1 + nfft/2)`
+    pub fn filterbank(&self) -> &MelFilterbank {
+        &self.filterbank
+    }
+
+    /// Compute the filterbank energies of the signal as
+    /// `python_speech_features.fbank` does. This returns the energies with
+    /// shape `(nfilt, frames)` and the total energy of each frame. Zeros are
+    /// replaced by a small epsilon so both are safe to take logarithms of.
+    /// Returns an error if the signal is empty
+    pub fn fbank<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+    ) -> Result<(Array2<f32>, Array1<f32>), Error> {
+        let pspec = self.power_spectrum(samples)?;
+        let energy = pspec.sum_axis(Axis(0)).mapv(replace_zero);
+        let feat = self.filterbank.apply(&pspec)?.mapv(replace_zero);
+        Ok((feat, energy))
+    }
+
+    /// Compute the log filterbank energies of the signal as
+    /// `python_speech_features.logfbank` does, returning an array of shape
+    /// `(nfilt, frames)`. Returns an error if the signal is empty
+    pub fn logfbank<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+    ) -> Result<Array2<f32>, Error> {
+        let (feat, _) = self.fbank(samples)?;
+        Ok(feat.mapv(f32::ln))
+    }
+
+    /// Compute the spectral subband centroids of the signal as
+    /// `python_speech_features.ssc` does, returning an array of shape
+    /// `(nfilt, frames)`. Each is the mean frequency of the power spectrum
+    /// weighted by one of the filters. Returns an error if the signal is empty
+    pub fn ssc<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Result<Array2<f32>, Error> {
+        let pspec = self.power_spectrum(samples)?.mapv(replace_zero);
+        let weights = self.filterbank.weights();
+        let feat = weights.dot(&pspec);
+        // python_speech_features starts the bin frequencies at 1Hz not 0Hz
+        let nyquist = self.filterbank.sample_rate() / 2.0;
+        let freq = Array1::linspace(1.0, nyquist, pspec.nrows());
+        Ok(weights.dot(&(pspec * &freq.insert_axis(Axis(1)))) / feat)
+    }
+
+    /// Preemphasise and frame the signal, returning the power spectrum of
+    /// each frame with shape `(1 + nfft/2, frames)`. This doesn't use the STFT
+    /// as python_speech_features truncates frames longer than `nfft`, such as
+    /// the default 25ms frames and 512 point FFT at 22050Hz, whereas the STFT
+    /// rejects windows longer than the FFT
+    fn power_spectrum<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+    ) -> Result<Array2<f32>, Error> {
+        if samples.is_empty() {
+            return Err(Error::SignalTooShort {
+                samples: 0,
+                required: 1,
+            });
+        }
+        let signal = samples
+            .iter()
+            .map(|x| x.to_f32().unwrap_or_default())
+            .collect::<Array1<f32>>()
+            .preemphasis(self.preemph);
+        let mut frames = frame_signal(
+            signal.as_slice().ok_or(Error::NonContiguous)?,
+            self.frame_len,
+            self.frame_step,
+        )?;
+        frames *= &self.window.view().insert_axis(Axis(1));
+        let nfft = self.filterbank.n_fft();
+        Ok(power_spectrum(&frames, nfft) / nfft as f32)
+    }
+}
+
+/// python_speech_features replaces zeros with the float64 epsilon before
+/// taking logarithms or dividing
+fn replace_zero(x: f32) -> f32 {
+    if x == 0.0 {
+        f64::EPSILON as f32
+    } else {
+        x
+    }
+}
+
+/// Round to the nearest integer with halves rounded up, as python_speech_features
+/// does when converting window lengths in seconds to samples
+fn round_half_up(x: f32) -> usize {
+    (x as f64 + 0.5).floor() as usize
+}
+
+/// Split the signal into overlapping frames of `frame_len` samples, returning
+/// an array of shape `(frame_len, frames)`. The end of the signal is zero
+/// padded so every sample appears in a frame and there's at least one frame
+fn frame_signal(signal: &[f32], frame_len: usize, frame_step: usize) -> Result<Array2<f32>, Error> {
+    if frame_step == 0 {
+        return Err(Error::ZeroHop);
+    }
+    let num_frames = if signal.len() <= frame_len {
+        1
+    } else {
+        1 + (signal.len() - frame_len).div_ceil(frame_step)
+    };
+    let mut padded = signal.to_vec();
+    padded.resize((num_frames - 1) * frame_step + frame_len, 0.0);
+    Ok(frame(&padded, frame_len, frame_step)?.to_owned())
+}
+
+/// Create the python_speech_features triangular mel filterbank with shape
+/// `(nfilt, 1 + nfft/2)`. Unlike the librosa filterbank the filter edges are
+/// snapped to FFT bins and the filters aren't normalised
+fn filterbank(
+    nfilt: usize,
+    nfft: usize,
+    sample_rate: f32,
+    lowfreq: f32,
+    highfreq: f32,
+) -> Array2<f32> {
+    let bins = MelScale::Htk
+        .mel_frequencies(nfilt + 2, lowfreq as f64, highfreq as f64)
+        .mapv(|f| ((nfft + 1) as f64 * f / sample_rate as f64).floor() as usize);
+
+    let mut fbank = Array2::zeros((nfilt, 1 + nfft / 2));
+    for (j, mut filter) in fbank.outer_iter_mut().enumerate() {
+        let (left, centre, right) = (bins[j], bins[j + 1], bins[j + 2]);
+        for i in left..centre.min(filter.len()) {
+            filter[i] = (i - left) as f32 / (centre - left) as f32;
+        }
+        for i in centre..right.min(filter.len()) {
+            filter[i] = (right - i) as f32 / (right - centre) as f32;
+        }
+    }
+    fbank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn framing() {
+        let signal = (1..=10).map(|x| x as f32).collect::<Vec<_>>();
+        let frames = frame_signal(&signal, 4, 3).unwrap();
+        let expected = array![
+            [1.0, 2.0, 3.0, 4.0],
+            [4.0, 5.0, 6.0, 7.0],
+            [7.0, 8.0, 9.0, 10.0]
+        ];
+        assert_eq!(frames, expected.t());
+
+        let frames = frame_signal(&signal, 4, 4).unwrap();
+        assert_eq!(frames.column(2), array![9.0, 10.0, 0.0, 0.0]);
+
+        let frames = frame_signal(&signal[..2], 4, 4).unwrap();
+        assert_eq!(frames, array![[1.0], [2.0], [0.0], [0.0]]);
+        assert_eq!(frame_signal(&signal, 4, 0), Err(Error::ZeroHop));
+    }
+
+    #[test]
+    fn speech_features_filterbank() {
+        // Generated via python_speech_features.get_filterbanks(4, 16, 8000)
+        let expected = array![
+            [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.5, 1.0, 0.5, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 0.6666667, 0.3333333, 0.0]
+        ];
+        let fbank = filterbank(4, 16, 8000.0, 0.0, 4000.0);
+        assert_eq!(fbank.dim(), expected.dim());
+        for (a, e) in fbank.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-6), "{} ~= {}", a, e);
+        }
+    }
+
+    #[test]
+    fn frame_power_spectrum() {
+        let features = SpeechFeaturesBuilder::new(1000.0)
+            .set_winlen(0.006)
+            .set_winstep(0.006)
+            .set_fft_num(4)
+            .set_preemphasis(0.0)
+            .build();
+        assert_eq!(features.frame_len(), 6);
+        // Frames longer than nfft are truncated, as in
+        // python_speech_features.sigproc.powspec([[1, 2, 3, 4, 5, 6],
+        // [7, 8, 9, 10, 11, 12]], 4)
+        let signal = (1..=12).map(|x| x as f32).collect::<Vec<_>>();
+        let pspec = features.power_spectrum(&signal).unwrap();
+        let expected = array![[25.0, 289.0], [2.0, 2.0], [1.0, 1.0]];
+        assert_eq!(pspec.dim(), expected.dim());
+        for (a, e) in pspec.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-4), "{} ~= {}", a, e);
+        }
+    }
+
+    #[test]
+    fn subband_centroids() {
+        let signal = (0..16000)
+            .map(|x| (x as f32 * 2.0 * std::f32::consts::PI * 1000.0 / 16000.0).sin())
+            .collect::<Vec<_>>();
+        let features = SpeechFeaturesBuilder::new(16000.0)
+            .set_filter_num(10)
+            .set_preemphasis(0.0)
+            .set_window(WindowingAlgorithm::Hamming)
+            .build();
+        assert_eq!(features.frame_len(), 400);
+        assert_eq!(features.frame_step(), 160);
+
+        let ssc = features.ssc(&signal).unwrap();
+        // 1 + ceil((16000 - 400)/160) frames
+        assert_eq!(ssc.dim(), (10, 99));
+        // Every centroid lies within its filter and those of the filters
+        // around the tone are pulled towards it
+        let weights = features.filterbank().weights();
+        let bin_width = 8000.0 / 256.0;
+        for (filter, centroids) in weights.outer_iter().zip(ssc.outer_iter()) {
+            let low = filter.iter().position(|x| *x > 0.0).unwrap();
+            let high = filter.iter().rposition(|x| *x > 0.0).unwrap();
+            let (low, high) = (low as f32 * bin_width, (high + 1) as f32 * bin_width);
+            assert!(centroids.iter().all(|x| *x >= low && *x <= high));
+            if low <= 1000.0 && high >= 1000.0 {
+                assert!(centroids
+                    .iter()
+                    .all(|x| (x - 1000.0).abs() < 2.0 * bin_width));
+            }
+        }
+
+        assert!(features.ssc::<f32>(&[]).is_err());
+    }
+
+    #[test]
+    fn filterbank_energies() {
+        let signal = (0..8000)
+            .map(|x| (x as f32 * 0.3).sin())
+            .collect::<Vec<_>>();
+        let features = SpeechFeaturesBuilder::new(8000.0)
+            .set_winlen(0.032)
+            .set_winstep(0.016)
+            .set_fft_num(256)
+            .build();
+        let (feat, energy) = features.fbank(&signal).unwrap();
+        // 1 + ceil((8000 - 256)/128) frames
+        assert_eq!(feat.dim(), (26, 62));
+        assert_eq!(energy.len(), 62);
+        assert!(feat.iter().chain(energy.iter()).all(|x| *x > 0.0));
+        // The filters overlap so each bin is counted by at most two of them
+        for (frame, total) in feat.columns().into_iter().zip(energy.iter()) {
+            assert!(frame.sum() <= 2.0 * total * (1.0 + 1e-5));
+        }
+
+        let log = features.logfbank(&signal).unwrap();
+        assert_eq!(log, feat.mapv(f32::ln));
+
+        // Silence is floored at epsilon rather than giving infinite logs
+        let (feat, energy) = features.fbank(&[0.0f32; 1000]).unwrap();
+        assert!(feat.iter().all(|x| *x == f64::EPSILON as f32));
+        assert!(energy.iter().all(|x| *x == f64::EPSILON as f32));
+        assert!(features.logfbank::<f32>(&[]).is_err());
+    }
+}
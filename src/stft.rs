@@ -1,67 +1,130 @@
-use ndarray::{prelude::*, s};
-use num_traits::{Bounded, Num, NumCast};
-use rustfft::{num_complex::Complex, FftPlanner};
-use std::f32::consts::PI;
+use crate::error::Error;
+use crate::frame::frame;
+use crate::sample::integer_normalisation;
+use crate::window::{WindowMode, WindowingAlgorithm};
+use ndarray::{prelude::*, s, Data};
+use num_traits::{Bounded, Float, FloatConst, Num, NumCast};
+use realfft::{ComplexToReal, FftNum, RealFftPlanner, RealToComplex};
+use rustfft::num_complex::Complex;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
-/// Padding mode for the audio signal
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Floating point precision the STFT and spectra are computed in. This is
+/// implemented for `f32` and `f64`
+pub trait StftFloat: Float + FloatConst + FftNum + Default + From<f32> {}
+
+impl<T> StftFloat for T where T: Float + FloatConst + FftNum + Default + From<f32> {}
+
+/// Padding mode for the audio signal. The modes match those of `numpy.pad`,
+/// including when the padding is longer than the signal
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum PadMode {
-    /// Don't apply any padding
+    /// Don't apply any padding. The signal won't be centred even if centring
+    /// is requested
     NoPad,
-    /// Reflect the signal
+    /// Pad with a constant value
+    Constant(f32),
+    /// Pad with zeros, the same as `Constant(0.0)`. This is the librosa default
+    Zeros,
+    /// Repeat the edge values of the signal
+    Edge,
+    /// Reflect the signal about the edge values, `[1, 2, 3]` becomes
+    /// `[3, 2, 1, 2, 3, 2, 1]`
     Reflect,
+    /// Reflect the signal including the edge values, `[1, 2, 3]` becomes
+    /// `[2, 1, 1, 2, 3, 3, 2]`
+    Symmetric,
+    /// Wrap the signal around so the end pads the start and vice versa
+    Wrap,
 }
 
-/// Windowing algorithm to be applied to the signal. While this only supports
-/// Hann windowing at the current time it can be extended to support any
-/// windowing functions provided by scipy
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum WindowingAlgorithm {
-    /// Implements the Hann function on a fourier signal
-    Hann(usize),
-}
+impl PadMode {
+    /// Pad a signal with `before` samples at the start and `after` samples at
+    /// the end. Padding an empty signal with a mode that repeats the signal
+    /// pads with zeros
+    pub fn pad<T>(&self, signal: &[T], before: usize, after: usize) -> Vec<T>
+    where
+        T: Copy + From<f32>,
+    {
+        let mut padded = Vec::with_capacity(before + signal.len() + after);
+        self.pad_into(signal, before, after, &mut padded);
+        padded
+    }
 
-impl WindowingAlgorithm {
-    /// Run the windowing algorithm on a given element
-    pub fn run(self, n: Complex<f32>) -> Complex<f32> {
-        let mut copy = n;
-        self.run_inplace(&mut copy);
-        copy
-    }
-
-    /// Run the windowing algorithm inplace on the given element
-    pub fn run_inplace(self, n: &mut Complex<f32>) {
-        match self {
-            Self::Hann(len) => {
-                if len == 0 {
-                    panic!("Window length cannot be zero");
-                } else if len == 1 {
-                    n.re = 1.0;
-                    n.im = 0.0;
-                } else {
-                    let m = if len % 2 == 0 {
-                        (len + 1) as f32
+    /// Pad a signal as `pad` does, writing the result into `out` so an
+    /// existing allocation can be reused. Any previous contents are cleared
+    pub fn pad_into<T>(&self, signal: &[T], before: usize, after: usize, out: &mut Vec<T>)
+    where
+        T: Copy + From<f32>,
+    {
+        out.clear();
+        let n = signal.len() as isize;
+        let constant = match self {
+            Self::NoPad => {
+                out.extend_from_slice(signal);
+                return;
+            }
+            Self::Constant(value) => Some(T::from(*value)),
+            Self::Zeros => Some(T::from(0.0)),
+            _ if n == 0 => Some(T::from(0.0)),
+            _ => None,
+        };
+        let sample = |p: isize| -> T {
+            if (0..n).contains(&p) {
+                return signal[p as usize];
+            }
+            if let Some(constant) = constant {
+                return constant;
+            }
+            let index = match self {
+                Self::Edge => p.max(0).min(n - 1),
+                Self::Reflect if n == 1 => 0,
+                Self::Reflect => {
+                    let period = 2 * (n - 1);
+                    let p = p.rem_euclid(period);
+                    if p < n {
+                        p
                     } else {
-                        len as f32
-                    };
-                    n.re = 0.5 - 0.5 * (2.0 * PI * n.re / m).cos();
-                    n.im = 0.5 - 0.5 * (2.0 * PI * n.im / m).cos();
+                        period - p
+                    }
                 }
-            }
-        }
+                Self::Symmetric => {
+                    let period = 2 * n;
+                    let p = p.rem_euclid(period);
+                    if p < n {
+                        p
+                    } else {
+                        period - 1 - p
+                    }
+                }
+                Self::Wrap => p.rem_euclid(n),
+                _ => unreachable!("Constant padding is handled above"),
+            };
+            signal[index as usize]
+        };
+        out.extend((-(before as isize)..n + after as isize).map(sample));
     }
 }
 
 ///Builds a ShortTimeFourierTransform instance using the supplied parameters
-///and setting sensible defaults for unset parameters
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
-pub struct StftBuilder {
+///and setting sensible defaults for unset parameters. `StftBuilder::new()`
+///computes in single precision, use `StftBuilder::<f64>::default()` for
+///double precision
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StftBuilder<F = f32> {
     n_fft: usize,
     win_length: Option<usize>,
     hop_length: Option<usize>,
     win_alg: Option<WindowingAlgorithm>,
+    win_mode: Option<WindowMode>,
     centred: Option<bool>,
     pad_mode: Option<PadMode>,
+    normalise: bool,
+    precision: PhantomData<F>,
 }
 
 impl StftBuilder {
@@ -69,14 +132,12 @@ impl StftBuilder {
     pub fn new() -> Self {
         Self {
             n_fft: 2048,
-            win_length: None,
-            hop_length: None,
-            win_alg: None,
-            centred: None,
-            pad_mode: None,
+            ..Self::default()
         }
     }
+}
 
+impl<F: StftFloat> StftBuilder<F> {
     /// Set the number of FFT bins
     pub fn set_fft_num(mut self, n: usize) -> Self {
         self.n_fft = n;
@@ -101,6 +162,12 @@ impl StftBuilder {
         self
     }
 
+    /// Set whether a symmetric or periodic window is generated
+    pub fn set_window_mode(mut self, mode: WindowMode) -> Self {
+        self.win_mode = Some(mode);
+        self
+    }
+
     /// Sets whether the signal should be centred
     pub fn set_centred(mut self, centred: bool) -> Self {
         self.centred = Some(centred);
@@ -113,6 +180,14 @@ impl StftBuilder {
         self
     }
 
+    /// Scale integer input to `[-1, 1)` by `2^(bits - 1)` before transforming,
+    /// as `samples_to_float` does, so the spectrogram doesn't depend on the
+    /// sample type. Floating point input is unaffected
+    pub fn set_normalise(mut self, normalise: bool) -> Self {
+        self.normalise = normalise;
+        self
+    }
+
     /// Build the ShortTimeFourierTransform instance. This uses the following
     /// derivation for the defaults as defined by librosa
     ///
@@ -120,51 +195,206 @@ impl StftBuilder {
     /// window_length default same as N_FFT value
     /// hop_length default window_length/4
     /// centred default True
-    /// pad_mode default Reflect
+    /// pad_mode default Zeros
     /// window_algorithm: Hann
-    pub fn build(self) -> ShortTimeFourierTransform {
+    /// window_mode: Periodic
+    /// normalise: False
+    ///
+    /// # Panics
+    ///
+    /// If the parameters are invalid, see `try_build` for a fallible version
+    pub fn build(self) -> ShortTimeFourierTransform<F> {
+        match self.try_build() {
+            Ok(stft) => stft,
+            Err(e) => panic!("Invalid STFT parameters: {}", e),
+        }
+    }
+
+    /// Build the ShortTimeFourierTransform instance using the same defaults as
+    /// `build`, returning an error if the hop length is zero, the window is
+    /// empty, the window is longer than the FFT or a custom window doesn't have
+    /// exactly `win_length` coefficients
+    pub fn try_build(self) -> Result<ShortTimeFourierTransform<F>, Error> {
         let n_fft = if self.n_fft > 0 { self.n_fft } else { 2048 };
         let win_length = self.win_length.unwrap_or(n_fft);
         let hop_length = self.hop_length.unwrap_or(win_length / 4);
-        let centred = self.centred.unwrap_or(true);
-        let pad_mode = self.pad_mode.unwrap_or(PadMode::Reflect);
-        let win_alg = self
-            .win_alg
-            .unwrap_or_else(|| WindowingAlgorithm::Hann(win_length));
-        ShortTimeFourierTransform {
+        if hop_length == 0 {
+            return Err(Error::ZeroHop);
+        }
+        if win_length == 0 {
+            return Err(Error::InvalidParameter(
+                "window length must be greater than zero".to_string(),
+            ));
+        }
+        if win_length > n_fft {
+            return Err(Error::WindowLongerThanFft { win_length, n_fft });
+        }
+        let pad_mode = self.pad_mode.unwrap_or(PadMode::Zeros);
+        // Without padding there's nothing to centre the frames with
+        let centred = self.centred.unwrap_or(true) && pad_mode != PadMode::NoPad;
+        let win_alg = self.win_alg.unwrap_or_default();
+        if let WindowingAlgorithm::Custom(coefs) = &win_alg {
+            if coefs.len() != win_length {
+                return Err(Error::InvalidParameter(format!(
+                    "custom window has {} coefficients but the window length is {}",
+                    coefs.len(),
+                    win_length
+                )));
+            }
+        }
+        let win_mode = self.win_mode.unwrap_or(WindowMode::Periodic);
+        let window = padded_window(&win_alg, win_mode, win_length, n_fft);
+        let mut planner = RealFftPlanner::new();
+        Ok(ShortTimeFourierTransform {
             n_fft,
             hop_length,
             win_length,
+            window,
             centred,
             pad_mode,
-            win_alg,
-        }
+            normalise: self.normalise,
+            fft: planner.plan_fft_forward(n_fft),
+            ifft: planner.plan_fft_inverse(n_fft),
+            scratch: StftScratch::default(),
+        })
+    }
+}
+
+/// Convert the input samples to floats, replacing the contents of `out`
+pub(crate) fn to_floats<T, F>(samples: &[T], out: &mut Vec<F>)
+where
+    T: Num + Bounded + NumCast,
+    F: StftFloat,
+{
+    out.clear();
+    extend_floats(samples, out, false);
+}
+
+/// Append the input samples to `out` as floats, scaling integers to `[-1, 1)`
+/// if `normalise` is set
+fn extend_floats<T, F>(samples: &[T], out: &mut Vec<F>, normalise: bool)
+where
+    T: Num + Bounded + NumCast,
+    F: StftFloat,
+{
+    let (offset, scale) = if normalise {
+        integer_normalisation::<T>().unwrap_or((0.0, 1.0))
+    } else {
+        (0.0, 1.0)
+    };
+    out.extend(samples.iter().map(|x| {
+        x.to_f64()
+            .and_then(|x| F::from_f64((x - offset) / scale))
+            .unwrap_or_default()
+    }));
+}
+
+/// Generate the window and centre it within a buffer of `n_fft` samples
+fn padded_window<F: StftFloat>(
+    win_alg: &WindowingAlgorithm,
+    win_mode: WindowMode,
+    win_length: usize,
+    n_fft: usize,
+) -> Array1<F> {
+    let window = win_alg.generate(win_length, win_mode);
+    if n_fft != win_length {
+        let mut padded = Array1::zeros(n_fft);
+        let win_start = (n_fft - win_length) / 2;
+        padded
+            .slice_mut(s![win_start..win_start + win_length])
+            .assign(&window);
+        padded
+    } else {
+        window
     }
 }
 
+/// Buffers reused between runs of the STFT to avoid allocating
+#[derive(Clone, Default)]
+struct StftScratch<F> {
+    /// Input signal converted to floats
+    signal: Vec<F>,
+    /// Signal after padding
+    padded: Vec<F>,
+    /// Buffers for transforming the frames
+    frames: FramesScratch<F>,
+}
+
+/// Buffers used to window and transform a single frame
+#[derive(Clone, Default)]
+struct FrameScratch<F> {
+    /// Single windowed frame being transformed
+    frame: Vec<F>,
+    /// The `1 + n_fft/2` bins of the transformed frame
+    spectrum: Vec<Complex<F>>,
+    /// Scratch space for the FFT
+    fft: Vec<Complex<F>>,
+}
+
+/// Buffers used to transform the frames of a signal one at a time
+#[cfg(not(feature = "rayon"))]
+type FramesScratch<F> = FrameScratch<F>;
+
+/// Buffers used to transform the frames of a signal, with a set for each chunk
+/// of frames transformed in parallel
+#[cfg(feature = "rayon")]
+type FramesScratch<F> = Vec<FrameScratch<F>>;
+
 /// Object to execute short time fourier transforms with the provided parameters.
-/// Building using the StftBuilder
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct ShortTimeFourierTransform {
+/// Building using the StftBuilder. The FFT plans and window are computed once
+/// when built and reused for every run. The transform is computed in single
+/// precision by default and can be built for `f64` where more accuracy is needed
+#[derive(Clone)]
+pub struct ShortTimeFourierTransform<F = f32> {
     n_fft: usize,
     hop_length: usize,
     win_length: usize,
-    win_alg: WindowingAlgorithm,
+    /// Window of `n_fft` samples with the windowing algorithm centred in it
+    window: Array1<F>,
     centred: bool,
     pad_mode: PadMode,
+    /// Whether integer input is scaled to `[-1, 1)`
+    normalise: bool,
+    /// Real to complex FFT producing only the non-negative frequencies
+    fft: Arc<dyn RealToComplex<F>>,
+    /// Complex to real FFT exploiting the hermitian symmetry of the spectrum
+    ifft: Arc<dyn ComplexToReal<F>>,
+    scratch: StftScratch<F>,
+}
+
+impl<F: StftFloat> fmt::Debug for ShortTimeFourierTransform<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShortTimeFourierTransform")
+            .field("n_fft", &self.n_fft)
+            .field("hop_length", &self.hop_length)
+            .field("win_length", &self.win_length)
+            .field("window", &self.window)
+            .field("centred", &self.centred)
+            .field("pad_mode", &self.pad_mode)
+            .field("normalise", &self.normalise)
+            .finish()
+    }
+}
+
+impl<F: StftFloat> PartialEq for ShortTimeFourierTransform<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n_fft == other.n_fft
+            && self.hop_length == other.hop_length
+            && self.win_length == other.win_length
+            && self.window == other.window
+            && self.centred == other.centred
+            && self.pad_mode == other.pad_mode
+            && self.normalise == other.normalise
+    }
 }
 
 impl Default for ShortTimeFourierTransform {
     fn default() -> Self {
-        let win_length = 16000 / 25;
-        Self {
-            n_fft: 1024,
-            hop_length: 16000 / 100,
-            win_length,
-            win_alg: WindowingAlgorithm::Hann(win_length),
-            centred: true,
-            pad_mode: PadMode::Reflect,
-        }
+        StftBuilder::new()
+            .set_fft_num(1024)
+            .set_window_len(16000 / 25)
+            .set_hop_len(16000 / 100)
+            .build()
     }
 }
 
@@ -173,110 +403,537 @@ impl ShortTimeFourierTransform {
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+impl<F: StftFloat> ShortTimeFourierTransform<F> {
+    /// Number of points in the FFT
+    pub fn n_fft(&self) -> usize {
+        self.n_fft
+    }
+
+    /// Length of the window applied to each frame
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// Number of samples between successive frames
+    pub fn hop_length(&self) -> usize {
+        self.hop_length
+    }
+
+    /// Number of frames the transform produces for a signal of the given
+    /// length
+    pub fn frame_count(&self, samples: usize) -> usize {
+        let padded = if self.centred {
+            samples + 2 * (self.n_fft / 2)
+        } else {
+            samples
+        };
+        if padded < self.n_fft {
+            0
+        } else {
+            1 + (padded - self.n_fft) / self.hop_length
+        }
+    }
+
+    /// Minimum number of samples needed to produce a frame
+    pub fn min_samples(&self) -> usize {
+        let padding = if self.centred {
+            2 * (self.n_fft / 2)
+        } else {
+            0
+        };
+        self.n_fft.saturating_sub(padding).max(2)
+    }
+
+    /// Run on a set of input samples. Returns an error if the signal is shorter
+    /// than `min_samples`
+    pub fn run<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+    ) -> Result<Array2<Complex<F>>, Error> {
+        let mut result = Array2::zeros((1 + self.n_fft / 2, self.frame_count(samples.len())));
+        let mut scratch = StftScratch::default();
+        self.process(samples, result.view_mut(), &mut scratch)?;
+        Ok(result)
+    }
+
+    /// Run on every channel of a signal with the samples along the last axis,
+    /// such as `(channels, samples)` or `(batch, channels, samples)`. The
+    /// output keeps the leading axes followed by the frequency and frame axes,
+    /// so a `(channels, samples)` input gives `(channels, 1 + n_fft/2, frames)`
+    /// as in librosa. Each channel must be contiguous in memory
+    pub fn run_multichannel<S, T, D>(
+        &self,
+        signal: &ArrayBase<S, D>,
+    ) -> Result<Array<Complex<F>, D::Larger>, Error>
+    where
+        S: Data<Elem = T>,
+        T: Num + Bounded + NumCast,
+        D: Dimension,
+    {
+        let ndim = signal.ndim();
+        if ndim == 0 {
+            return Err(Error::InvalidParameter(
+                "signal must have a samples axis".to_string(),
+            ));
+        }
+        let samples = signal.len_of(Axis(ndim - 1));
+        let frames = self.frame_count(samples);
+        if samples < 2 || frames == 0 {
+            return Err(Error::SignalTooShort {
+                samples,
+                required: self.min_samples(),
+            });
+        }
+
+        let bins = 1 + self.n_fft / 2;
+        let channels = signal.len() / samples;
+        let mut result = Array3::zeros((channels, bins, frames));
+        let lanes = signal.lanes(Axis(ndim - 1)).into_iter();
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut scratch = StftScratch::default();
+            for (lane, out) in lanes.zip(result.outer_iter_mut()) {
+                let data = lane.as_slice().ok_or(Error::NonContiguous)?;
+                self.process(data, out, &mut scratch)?;
+            }
+        }
+        #[cfg(feature = "rayon")]
+        {
+            // The samples type may not be shareable between threads so the
+            // channels are converted to floats before being processed
+            let mut signals = Vec::with_capacity(channels);
+            for lane in lanes {
+                let mut data = Vec::with_capacity(samples);
+                let lane = lane.as_slice().ok_or(Error::NonContiguous)?;
+                extend_floats(lane, &mut data, self.normalise);
+                signals.push(data);
+            }
+            signals
+                .par_iter()
+                .zip(result.axis_iter_mut(Axis(0)).into_par_iter())
+                .for_each_init(
+                    || (vec![], FramesScratch::default()),
+                    |(padded, scratch), (signal, out)| {
+                        self.apply_padding(signal, padded);
+                        self.transform_frames(padded, out, scratch);
+                    },
+                );
+        }
+
+        let mut shape = signal.shape()[..ndim - 1].to_vec();
+        shape.extend_from_slice(&[bins, frames]);
+        Ok(result
+            .into_shape(shape)
+            .and_then(|x| x.into_dimensionality())
+            .expect("Output has one more axis than the input"))
+    }
+
+    /// Run on a set of input samples writing the result into `out`, which must
+    /// have shape `(1 + n_fft/2, frame_count(samples.len()))`. Buffers are kept
+    /// between calls so once they've grown to fit the signal no allocations are
+    /// made. Returns an error if the signal is too short or the output is the
+    /// wrong shape
+    pub fn run_into<T: Num + Bounded + NumCast>(
+        &mut self,
+        samples: &[T],
+        out: ArrayViewMut2<Complex<F>>,
+    ) -> Result<(), Error> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let result = self.process(samples, out, &mut scratch);
+        self.scratch = scratch;
+        result
+    }
+
+    fn process<T: Num + Bounded + NumCast>(
+        &self,
+        samples: &[T],
+        out: ArrayViewMut2<Complex<F>>,
+        scratch: &mut StftScratch<F>,
+    ) -> Result<(), Error> {
+        let frames = self.frame_count(samples.len());
+        if samples.len() < 2 || frames == 0 {
+            return Err(Error::SignalTooShort {
+                samples: samples.len(),
+                required: self.min_samples(),
+            });
+        }
+        let dim = (1 + self.n_fft / 2, frames);
+        if out.dim() != dim {
+            return Err(Error::InvalidParameter(format!(
+                "output has shape {:?} but {:?} is needed",
+                out.dim(),
+                dim
+            )));
+        }
+
+        scratch.signal.clear();
+        extend_floats(samples, &mut scratch.signal, self.normalise);
+        self.apply_padding(&scratch.signal, &mut scratch.padded);
+        self.transform_frames(&scratch.padded, out, &mut scratch.frames);
+        Ok(())
+    }
+
+    /// Window and transform the frames of an already padded signal, with the
+    /// first frame starting at the beginning of `padded`. One frame is written
+    /// to each column of `out`
+    #[cfg(not(feature = "rayon"))]
+    fn transform_frames(
+        &self,
+        padded: &[F],
+        out: ArrayViewMut2<Complex<F>>,
+        scratch: &mut FramesScratch<F>,
+    ) {
+        if out.ncols() == 0 {
+            return;
+        }
+        let frames = self.frame_view(padded, out.ncols());
+        self.transform_chunk(frames, out, scratch);
+    }
+
+    /// Window and transform the frames of an already padded signal, with the
+    /// first frame starting at the beginning of `padded`. One frame is written
+    /// to each column of `out`. The frames are split into a contiguous chunk
+    /// for each thread, with each chunk reusing one set of buffers in `scratch`
+    #[cfg(feature = "rayon")]
+    fn transform_frames(
+        &self,
+        padded: &[F],
+        mut out: ArrayViewMut2<Complex<F>>,
+        scratch: &mut FramesScratch<F>,
+    ) {
+        if out.ncols() == 0 {
+            return;
+        }
+        let frames = self.frame_view(padded, out.ncols());
+        let chunk = out.ncols().div_ceil(rayon::current_num_threads());
+        let chunks = out.ncols().div_ceil(chunk);
+        if scratch.len() < chunks {
+            scratch.resize_with(chunks, FrameScratch::default);
+        }
+        frames
+            .axis_chunks_iter(Axis(1), chunk)
+            .into_par_iter()
+            .zip(out.axis_chunks_iter_mut(Axis(1), chunk))
+            .zip(scratch.par_iter_mut())
+            .for_each(|((frames, out), scratch)| self.transform_chunk(frames, out, scratch));
+    }
+
+    /// View the first `count` frames of an already padded signal, with the
+    /// first frame starting at the beginning of `padded`
+    fn frame_view<'a>(&self, padded: &'a [F], count: usize) -> ArrayView2<'a, F> {
+        frame(padded, self.n_fft, self.hop_length)
+            .expect("padded signal is long enough for every output frame")
+            .slice_move(s![.., ..count])
+    }
+
+    /// Window and transform each column of `frames` into the same column of
+    /// `out` in turn
+    fn transform_chunk(
+        &self,
+        frames: ArrayView2<'_, F>,
+        mut out: ArrayViewMut2<Complex<F>>,
+        scratch: &mut FrameScratch<F>,
+    ) {
+        for (frame, col) in frames.axis_iter(Axis(1)).zip(out.axis_iter_mut(Axis(1))) {
+            // Frames are contiguous as the samples in them are adjacent
+            self.transform_frame(frame.to_slice().unwrap(), col, scratch);
+        }
+    }
+
+    /// Window and transform a single frame of `n_fft` samples
+    fn transform_frame(
+        &self,
+        input: &[F],
+        mut out: ArrayViewMut1<Complex<F>>,
+        scratch: &mut FrameScratch<F>,
+    ) {
+        scratch.frame.resize(self.n_fft, F::zero());
+        scratch
+            .spectrum
+            .resize(1 + self.n_fft / 2, Complex::default());
+        scratch
+            .fft
+            .resize(self.fft.get_scratch_len(), Complex::default());
 
-    /// Run on a set of input samples
-    pub fn run<T: Num + Bounded + NumCast>(&self, samples: &[T]) -> Option<Array2<Complex<f32>>> {
-        if samples.len() < 2 {
-            return None;
+        for ((f, x), w) in scratch
+            .frame
+            .iter_mut()
+            .zip(input.iter())
+            .zip(self.window.iter())
+        {
+            *f = *x * *w;
         }
-        let mut input: Vec<Complex<f32>> = samples
-            .iter()
-            .map(|x| Complex::new(x.to_f32().unwrap_or_default(), 0.0))
-            .collect();
+        self.fft
+            .process_with_scratch(&mut scratch.frame, &mut scratch.spectrum, &mut scratch.fft)
+            .expect("Buffers are sized by the FFT plan");
+        out.assign(&ArrayView1::from(&scratch.spectrum));
+    }
 
+    /// Run the inverse transform on a spectrogram produced by `run`, returning
+    /// the reconstructed time domain signal. Each frame is inverted, weighted by
+    /// the synthesis window and overlap-added before being normalised by the sum
+    /// of the squared window as in librosa. Any centre padding is removed and if
+    /// a length is provided the output is trimmed or zero-padded to match it.
+    ///
+    /// Returns an error if the number of rows doesn't match `1 + n_fft/2` or
+    /// there are no frames
+    pub fn istft<S>(
+        &self,
+        stft_matrix: &ArrayBase<S, Ix2>,
+        length: Option<usize>,
+    ) -> Result<Array1<F>, Error>
+    where
+        S: Data<Elem = Complex<F>>,
+    {
         let rows = 1 + self.n_fft / 2;
+        let (n_rows, n_frames) = stft_matrix.dim();
+        if n_rows != rows {
+            return Err(Error::InvalidParameter(format!(
+                "spectrogram has {} frequency bins but {} are needed",
+                n_rows, rows
+            )));
+        }
+        if n_frames == 0 {
+            return Err(Error::SignalTooShort {
+                samples: 0,
+                required: 1,
+            });
+        }
 
-        self.apply_padding(&mut input);
-
-        let window_mat = self.get_window_matrix();
-        // Not implementing memory limiting initially
-        let frame_len = (input.len() - self.n_fft + 1) as f32 / self.hop_length as f32;
-        let frame_len = frame_len.ceil() as usize;
-
-        let mut result = unsafe { Array2::uninitialized((rows, frame_len)) };
-        let mut scratchpad = unsafe { Array2::uninitialized((self.n_fft, frame_len)) };
-        let fft = FftPlanner::new().plan_fft_forward(self.n_fft);
-
-        for i in 0..self.n_fft {
-            let win_coef = window_mat[i];
-            let data = input
-                .iter()
-                .skip(i)
-                .step_by(self.hop_length)
-                .take(frame_len)
-                .map(|x| *x * win_coef)
-                .collect::<Vec<_>>();
-
-            scratchpad.row_mut(i).assign(&Array1::from(data));
-        }
-
-        let mut temp = Vec::with_capacity(self.n_fft);
-        for (idx, mut col) in result.lanes_mut(Axis(0)).into_iter().enumerate() {
-            let mut column = scratchpad.column(idx).into_owned();
-            temp.clear();
-            temp.resize(self.n_fft, Complex::new(0.0, 0.0));
-            if let Some(c) = column.view_mut().into_slice() {
-                fft.process(c);
-                temp.clone_from_slice(c);
+        let window = &self.window;
+        let expected_len = self.n_fft + self.hop_length * (n_frames - 1);
+        let mut output = Array1::<F>::zeros(expected_len);
+        let mut win_sum_square = Array1::<F>::zeros(expected_len);
+        // realfft doesn't normalise the inverse transform
+        let norm = F::one() / <F as NumCast>::from(self.n_fft).unwrap_or_else(F::one);
+
+        let mut spectrum = self.ifft.make_input_vec();
+        let mut buffer = self.ifft.make_output_vec();
+        let mut scratch = self.ifft.make_scratch_vec();
+        for (idx, column) in stft_matrix.axis_iter(Axis(1)).enumerate() {
+            spectrum
+                .iter_mut()
+                .zip(column.iter())
+                .for_each(|(b, x)| *b = *x);
+            // The imaginary parts of the DC and Nyquist bins of a real signal
+            // are zero, any values there are ignored as numpy does
+            spectrum[0].im = F::zero();
+            if self.n_fft.is_multiple_of(2) {
+                spectrum[rows - 1].im = F::zero();
+            }
+            self.ifft
+                .process_with_scratch(&mut spectrum, &mut buffer, &mut scratch)
+                .expect("DC and Nyquist bins are real");
+
+            let start = idx * self.hop_length;
+            let mut out_frame = output.slice_mut(s![start..start + self.n_fft]);
+            for ((o, b), w) in out_frame.iter_mut().zip(buffer.iter()).zip(window.iter()) {
+                *o = *o + *b * norm * *w;
+            }
+            let mut sum_frame = win_sum_square.slice_mut(s![start..start + self.n_fft]);
+            sum_frame.zip_mut_with(window, |s, w| *s = *s + *w * *w);
+        }
+
+        output.zip_mut_with(&win_sum_square, |o, w| {
+            if *w > F::min_positive_value() {
+                *o = *o / *w;
+            }
+        });
+
+        let start = if self.centred { self.n_fft / 2 } else { 0 };
+        match length {
+            Some(length) => {
+                let mut result = Array1::zeros(length);
+                let end = expected_len.min(start + length);
+                result
+                    .slice_mut(s![..end - start])
+                    .assign(&output.slice(s![start..end]));
+                Ok(result)
+            }
+            None => {
+                let end = if self.centred {
+                    expected_len - self.n_fft / 2
+                } else {
+                    expected_len
+                };
+                Ok(output.slice(s![start..end]).to_owned())
             }
-            // Get rid of the mirrored values
-            temp.resize(rows, Complex::new(0.0, 0.0));
-            col.assign(&Array1::from(temp.clone()));
-        }
-        Some(result)
-    }
-
-    /// Get the signal matrix post windowing
-    fn get_window_matrix(&self) -> Array1<Complex<f32>> {
-        let result: Array1<f32> =
-            Array1::linspace(0.0, self.win_length as f32 - 1.0, self.win_length);
-        let mut result: Array1<Complex<f32>> = result.mapv(|x| Complex::new(x, 0.0));
-        result.mapv_inplace(|x| self.win_alg.run(x));
-        if self.n_fft != self.win_length {
-            let mut padded_result = Array1::from_elem(self.n_fft, Complex::new(0.0, 0.0));
-            let win_start = (self.n_fft - self.win_length) / 2;
-            padded_result
-                .slice_mut(s![win_start..win_start + self.win_length])
-                .assign(&result);
-            padded_result
+        }
+    }
+
+    /// Apply padding to the input signal, writing the padded signal into
+    /// `padded`
+    fn apply_padding(&self, signal: &[F], padded: &mut Vec<F>) {
+        let pad_width = if self.centred { self.n_fft / 2 } else { 0 };
+        self.pad_mode.pad_into(signal, pad_width, pad_width, padded);
+    }
+}
+
+/// Incremental STFT for live audio. Samples can be pushed in chunks of any
+/// size and every frame that can be computed from the samples seen so far is
+/// returned. Calling `flush` at the end of the signal applies the end padding
+/// and returns the remaining frames, so the frames from all the pushes and the
+/// flush concatenated along the frame axis are the same as `run` on the whole
+/// signal.
+///
+/// When the signal is centred the first frames are held back until more than
+/// `n_fft/2` samples have arrived, as the start padding depends on them. With
+/// `PadMode::Wrap` the start padding depends on the end of the signal so no
+/// frames are emitted until the stream is flushed
+#[derive(Clone)]
+pub struct StreamingStft<F = f32> {
+    stft: ShortTimeFourierTransform<F>,
+    /// Samples not yet consumed by a frame. Once started this is the padded
+    /// signal starting at `offset`, before then it's the raw signal
+    buffer: Vec<F>,
+    offset: usize,
+    /// Number of samples pushed since the start of the signal
+    received: usize,
+    /// Whether the start padding has been applied to the buffer
+    started: bool,
+    /// Index of the next frame to be emitted
+    next_frame: usize,
+    scratch: FramesScratch<F>,
+}
+
+impl<F: StftFloat> fmt::Debug for StreamingStft<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingStft")
+            .field("stft", &self.stft)
+            .field("buffered", &self.buffer.len())
+            .field("received", &self.received)
+            .field("next_frame", &self.next_frame)
+            .finish()
+    }
+}
+
+impl<F: StftFloat> From<ShortTimeFourierTransform<F>> for StreamingStft<F> {
+    fn from(stft: ShortTimeFourierTransform<F>) -> Self {
+        Self::new(stft)
+    }
+}
+
+impl<F: StftFloat> StreamingStft<F> {
+    /// Create a streaming STFT using the parameters of the given transform
+    pub fn new(stft: ShortTimeFourierTransform<F>) -> Self {
+        Self {
+            stft,
+            buffer: vec![],
+            offset: 0,
+            received: 0,
+            started: false,
+            next_frame: 0,
+            scratch: FramesScratch::default(),
+        }
+    }
+
+    /// The transform the frames are computed with
+    pub fn stft(&self) -> &ShortTimeFourierTransform<F> {
+        &self.stft
+    }
+
+    /// Push the next chunk of the signal, returning any frames completed by
+    /// it as an array of shape `(1 + n_fft/2, frames)`. The array has no
+    /// columns if no frames were completed
+    pub fn push<T: Num + Bounded + NumCast>(&mut self, samples: &[T]) -> Array2<Complex<F>> {
+        extend_floats(samples, &mut self.buffer, self.stft.normalise);
+        self.received += samples.len();
+
+        let pad_width = self.pad_width();
+        if !self.started && self.received > pad_width && self.stft.pad_mode != PadMode::Wrap {
+            // Reflecting about the start only needs the first n_fft/2 + 1
+            // samples so the start padding is final once they've arrived
+            self.buffer = self.stft.pad_mode.pad(&self.buffer, pad_width, 0);
+            self.started = true;
+        }
+        if self.started {
+            self.emit_frames()
         } else {
-            result
+            Array2::zeros((1 + self.stft.n_fft / 2, 0))
         }
     }
 
-    /// Apply padding to the input signal
-    fn apply_padding(&self, arr: &mut Vec<Complex<f32>>) {
-        if self.centred {
-            // Apply padding
-            let pad_width = self.n_fft / 2;
-            if self.pad_mode == PadMode::Reflect {
-                let mut pos = (arr.len() - 2) as i64;
-                let mut delta = -1;
-                for _ in 0..pad_width {
-                    arr.push(arr[pos as usize]);
+    /// Finish the signal by applying the end padding, returning the remaining
+    /// frames as an array of shape `(1 + n_fft/2, frames)`. The stream is
+    /// reset afterwards ready for a new signal
+    pub fn flush(&mut self) -> Array2<Complex<F>> {
+        let pad_width = self.pad_width();
+        let frames = if self.started {
+            // The buffer always keeps the last n_fft/2 + 1 samples the end
+            // padding is made from
+            let tail_start = self.buffer.len() - (pad_width + 1).min(self.buffer.len());
+            let tail = &self.buffer[tail_start..];
+            let padded = self.stft.pad_mode.pad(tail, 0, pad_width);
+            self.buffer.extend_from_slice(&padded[tail.len()..]);
+            self.emit_frames()
+        } else if self.received >= 2 {
+            // Either a very short signal or wrap padding, in which case the
+            // whole signal is in the buffer
+            self.buffer = self.stft.pad_mode.pad(&self.buffer, pad_width, pad_width);
+            self.emit_frames()
+        } else {
+            Array2::zeros((1 + self.stft.n_fft / 2, 0))
+        };
+        self.reset();
+        frames
+    }
 
-                    if pos == 0 {
-                        delta = 1;
-                    } else if pos == arr.len() as i64 - 1 {
-                        delta = -1;
-                    }
-                    pos += delta;
-                }
-                let mut pos = 1;
-                // We've padded to the end so won't overflow
-                for i in 0..pad_width {
-                    arr.insert(0, arr[pos + i]);
-                    pos += 1;
-                }
-            }
+    /// Discard any buffered samples and start a new signal
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.offset = 0;
+        self.received = 0;
+        self.started = false;
+        self.next_frame = 0;
+    }
+
+    fn pad_width(&self) -> usize {
+        if self.stft.centred {
+            self.stft.n_fft / 2
+        } else {
+            0
+        }
+    }
+
+    /// Transform every complete frame in the buffer and drop the samples no
+    /// longer needed
+    fn emit_frames(&mut self) -> Array2<Complex<F>> {
+        let n_fft = self.stft.n_fft;
+        let hop_length = self.stft.hop_length;
+        let end = self.offset + self.buffer.len();
+        let first_start = self.next_frame * hop_length;
+        let frames = if first_start + n_fft <= end {
+            1 + (end - first_start - n_fft) / hop_length
+        } else {
+            0
+        };
+
+        let mut result = Array2::zeros((1 + n_fft / 2, frames));
+        if frames > 0 {
+            let padded = &self.buffer[first_start - self.offset..];
+            self.stft
+                .transform_frames(padded, result.view_mut(), &mut self.scratch);
         }
+        self.next_frame += frames;
+
+        let next_start = self.next_frame * hop_length - self.offset;
+        let keep = self.pad_width() + 1;
+        let drain = next_start.min(self.buffer.len().saturating_sub(keep));
+        self.buffer.drain(..drain);
+        self.offset += drain;
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sample::{float_to_samples, Dither};
     use float_cmp::approx_eq;
+    use std::f32::consts::PI;
 
     #[test]
     fn representative_usage() {
@@ -291,71 +948,341 @@ mod tests {
             .set_centred(true)
             .build();
 
-        assert!(stft.run(data.as_slice().unwrap()).is_some())
+        assert!(stft.run(data.as_slice().unwrap()).is_ok())
     }
 
     #[test]
     fn signal_padding() {
-        let mut data = vec![
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(5.0, 0.0),
-        ];
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let small = StftBuilder::new()
+            .set_fft_num(5)
+            .set_centred(true)
+            .set_padding_mode(PadMode::Reflect)
+            .build();
 
-        let small = StftBuilder::new().set_fft_num(5).set_centred(true).build();
+        let mut padded = vec![];
+        small.apply_padding(&data, &mut padded);
+
+        let expected = vec![3.0, 2.0, 1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0];
+
+        assert_eq!(padded, expected);
+    }
+
+    #[test]
+    fn long_signal_padding() {
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let small = StftBuilder::new()
+            .set_fft_num(10)
+            .set_centred(true)
+            .set_padding_mode(PadMode::Reflect)
+            .build();
 
-        small.apply_padding(&mut data);
+        let mut padded = vec![];
+        small.apply_padding(&data, &mut padded);
 
         let expected = vec![
-            Complex::new(3.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(5.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(3.0, 0.0),
+            4.0, 5.0, 4.0, 3.0, 2.0, 1.0, 2.0, 3.0, 4.0, 5.0, 4.0, 3.0, 2.0, 1.0, 2.0,
         ];
 
-        assert_eq!(data, expected);
+        assert_eq!(padded, expected);
     }
 
     #[test]
-    fn long_signal_padding() {
-        let mut data = vec![
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(5.0, 0.0),
+    fn run_into_reuses_buffers() {
+        let mut stft = StftBuilder::new().set_fft_num(64).set_hop_len(16).build();
+        let signal = (0..500).map(|x| (x as f32 * 0.1).sin()).collect::<Vec<_>>();
+        let expected = stft.run(&signal).unwrap();
+
+        let frames = stft.frame_count(signal.len());
+        let mut out = Array2::zeros((33, frames));
+        for _ in 0..3 {
+            stft.run_into(&signal, out.view_mut()).unwrap();
+            assert_eq!(out, expected);
+        }
+        // The frame buffers are kept on the STFT, with a set per chunk of
+        // frames when they're transformed in parallel
+        #[cfg(not(feature = "rayon"))]
+        assert_eq!(stft.scratch.frames.frame.len(), 64);
+        #[cfg(feature = "rayon")]
+        {
+            assert!(!stft.scratch.frames.is_empty());
+            assert!(stft.scratch.frames.iter().all(|x| x.frame.len() == 64));
+        }
+
+        // Shorter signals reuse the larger buffers
+        let frames = stft.frame_count(100);
+        let mut out = Array2::zeros((33, frames));
+        stft.run_into(&signal[..100], out.view_mut()).unwrap();
+        assert_eq!(out, stft.run(&signal[..100]).unwrap());
+
+        let mut wrong_shape = Array2::zeros((33, frames + 1));
+        assert!(matches!(
+            stft.run_into(&signal[..100], wrong_shape.view_mut()),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn normalised_integer_input() {
+        let signal = (0..500)
+            .map(|x| (x as f32 * 0.1).sin() * 0.5)
+            .collect::<Vec<_>>();
+        let quantised = float_to_samples::<i16, _>(&signal, Dither::None);
+        let unsigned = float_to_samples::<u8, _>(&signal, Dither::None);
+        let builder = StftBuilder::new().set_fft_num(64).set_hop_len(16);
+        let expected = builder.clone().build().run(&signal).unwrap();
+
+        let stft = builder.clone().set_normalise(true).build();
+        // Float input is unaffected
+        assert_eq!(stft.run(&signal).unwrap(), expected);
+        for (x, tol) in [
+            (stft.run(&quantised).unwrap(), 1e-3),
+            (stft.run(&unsigned).unwrap(), 0.1),
+        ]
+        .iter()
+        {
+            for (a, e) in x.iter().zip(expected.iter()) {
+                assert!((a - e).norm() < *tol, "{} ~= {}", a, e);
+            }
+        }
+
+        let raw = builder.build().run(&quantised).unwrap();
+        for (a, e) in raw.iter().zip(expected.iter()) {
+            assert!((a / 32768.0 - e).norm() < 1e-3, "{} ~= {}", a, e);
+        }
+
+        let mut streaming = StreamingStft::new(stft.clone());
+        let mut frames = streaming.push(&quantised);
+        let flushed = streaming.flush();
+        frames.append(Axis(1), flushed.view()).unwrap();
+        assert_eq!(frames, stft.run(&quantised).unwrap());
+    }
+
+    #[test]
+    fn real_fft_matches_complex_fft() {
+        use rustfft::FftPlanner;
+
+        let signal = (0..300)
+            .map(|x| (x as f32 * 0.05).sin() + 0.25 * (x as f32 * 0.7).cos())
+            .collect::<Vec<_>>();
+        for n_fft in [16, 17, 64].iter() {
+            let stft = StftBuilder::new().set_fft_num(*n_fft).build();
+            let result = stft.run(&signal).unwrap();
+
+            let padded = PadMode::Zeros.pad(&signal, n_fft / 2, n_fft / 2);
+            let fft = FftPlanner::new().plan_fft_forward(*n_fft);
+            for (idx, col) in result.axis_iter(Axis(1)).enumerate() {
+                let start = idx * stft.hop_length();
+                let mut frame = padded[start..start + n_fft]
+                    .iter()
+                    .zip(stft.window.iter())
+                    .map(|(x, w)| Complex::new(x * w, 0.0))
+                    .collect::<Vec<_>>();
+                fft.process(&mut frame);
+                for (a, e) in col.iter().zip(frame.iter()) {
+                    assert!(approx_eq!(f32, a.re, e.re, epsilon = 1e-5));
+                    assert!(approx_eq!(f32, a.im, e.im, epsilon = 1e-5));
+                }
+            }
+        }
+    }
+
+    fn run_streaming(
+        stream: &mut StreamingStft,
+        signal: &[f32],
+        chunks: &[usize],
+    ) -> Array2<Complex<f32>> {
+        let mut outputs = vec![];
+        let mut start = 0;
+        for chunk in chunks.iter().cycle() {
+            if start >= signal.len() {
+                break;
+            }
+            let end = (start + chunk).min(signal.len());
+            outputs.push(stream.push(&signal[start..end]));
+            start = end;
+        }
+        outputs.push(stream.flush());
+        let views = outputs.iter().map(|x| x.view()).collect::<Vec<_>>();
+        ndarray::concatenate(Axis(1), &views).unwrap()
+    }
+
+    #[test]
+    fn streaming_matches_run() {
+        let signal = (0..1000)
+            .map(|x| (x as f32 * 0.03).sin() + 0.2 * (x as f32 * 0.9).cos())
+            .collect::<Vec<_>>();
+        let chunks = [1, 7, 160, 3, 333, 50];
+        let modes = [
+            PadMode::Zeros,
+            PadMode::Reflect,
+            PadMode::Symmetric,
+            PadMode::Edge,
+            PadMode::Wrap,
+            PadMode::Constant(0.5),
+            PadMode::NoPad,
         ];
+        for mode in modes.iter() {
+            for (n_fft, hop_length) in [(64, 16), (32, 40), (5, 1), (256, 100)].iter() {
+                let stft = StftBuilder::new()
+                    .set_fft_num(*n_fft)
+                    .set_hop_len(*hop_length)
+                    .set_padding_mode(*mode)
+                    .build();
+                let mut stream = StreamingStft::new(stft.clone());
+                for len in [1000, 20, 3].iter() {
+                    let expected = stft
+                        .run(&signal[..*len])
+                        .unwrap_or_else(|_| Array2::zeros((n_fft / 2 + 1, 0)));
+                    let result = run_streaming(&mut stream, &signal[..*len], &chunks);
+                    assert_eq!(
+                        result, expected,
+                        "{:?} {} {} {}",
+                        mode, n_fft, hop_length, len
+                    );
+                }
+            }
+        }
+    }
 
-        let small = StftBuilder::new().set_fft_num(10).set_centred(true).build();
+    #[test]
+    fn streaming_emits_frames_early() {
+        let stft = StftBuilder::new()
+            .set_fft_num(16)
+            .set_hop_len(4)
+            .set_padding_mode(PadMode::Reflect)
+            .build();
+        let mut stream = StreamingStft::from(stft);
+        // Nothing can be emitted until the start padding is known
+        assert_eq!(stream.push(&[0.0f32; 8]).ncols(), 0);
+        // 8 padding + 9 samples covers the first frame
+        assert_eq!(stream.push(&[0.0f32; 1]).ncols(), 1);
+        assert_eq!(stream.push(&[0.0f32; 8]).ncols(), 2);
+        // The final frames need the end padding
+        assert_eq!(stream.flush().ncols(), 2);
+        assert_eq!(stream.flush().ncols(), 0);
+    }
 
-        small.apply_padding(&mut data);
+    #[test]
+    fn multichannel_matches_single_channel() {
+        let stft = StftBuilder::new().set_fft_num(64).set_hop_len(16).build();
+        let signal = Array3::from_shape_fn((2, 3, 500), |(b, c, x)| {
+            ((x as f32) * 0.01 * (1 + b + 2 * c) as f32).sin()
+        });
 
-        let expected = vec![
-            Complex::new(4.0, 0.0),
-            Complex::new(5.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(5.0, 0.0),
-            Complex::new(4.0, 0.0),
-            Complex::new(3.0, 0.0),
-            Complex::new(2.0, 0.0),
-            Complex::new(1.0, 0.0),
-            Complex::new(2.0, 0.0),
+        let batch = stft.run_multichannel(&signal).unwrap();
+        let frames = stft.frame_count(500);
+        assert_eq!(batch.dim(), (2, 3, 33, frames));
+        for (b, channels) in signal.outer_iter().enumerate() {
+            let result = stft.run_multichannel(&channels).unwrap();
+            assert_eq!(result.dim(), (3, 33, frames));
+            for (c, channel) in channels.outer_iter().enumerate() {
+                let expected = stft.run(channel.as_slice().unwrap()).unwrap();
+                assert_eq!(result.index_axis(Axis(0), c), expected);
+                assert_eq!(batch.slice(s![b, c, .., ..]), expected);
+            }
+        }
+
+        let single = stft.run_multichannel(&signal.slice(s![0, 0, ..])).unwrap();
+        assert_eq!(
+            single,
+            stft.run(&signal.slice(s![0, 0, ..]).to_vec()).unwrap()
+        );
+
+        let transposed = signal.index_axis(Axis(0), 0).reversed_axes();
+        assert_eq!(
+            stft.run_multichannel(&transposed),
+            Err(Error::NonContiguous)
+        );
+        assert!(matches!(
+            stft.run_multichannel(&Array2::<f32>::zeros((2, 1))),
+            Err(Error::SignalTooShort { samples: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn frames_match_serial_transform() {
+        // With the rayon feature the frames are computed in parallel and
+        // should be identical to transforming them one at a time
+        let stft = StftBuilder::new().set_fft_num(128).set_hop_len(32).build();
+        let signal = (0..10000)
+            .map(|x| (x as f32 * 0.02).sin() * (x as f32 * 0.001).cos())
+            .collect::<Vec<_>>();
+        let result = stft.run(&signal).unwrap();
+
+        let padded = PadMode::Zeros.pad(&signal, 64, 64);
+        let mut scratch = FrameScratch::default();
+        let mut expected = Array1::zeros(65);
+        for (idx, col) in result.axis_iter(Axis(1)).enumerate() {
+            let start = idx * 32;
+            stft.transform_frame(
+                &padded[start..start + 128],
+                expected.view_mut(),
+                &mut scratch,
+            );
+            assert_eq!(col, expected);
+        }
+    }
+
+    #[test]
+    fn numpy_padding_modes() {
+        // Generated via np.pad([1.0, 2.0, 3.0], 4, mode)
+        let data = [1.0f32, 2.0, 3.0];
+        let cases = [
+            (
+                PadMode::Reflect,
+                vec![1.0, 2.0, 3.0, 2.0, 1.0, 2.0, 3.0, 2.0, 1.0, 2.0, 3.0],
+            ),
+            (
+                PadMode::Symmetric,
+                vec![3.0, 3.0, 2.0, 1.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, 1.0],
+            ),
+            (
+                PadMode::Wrap,
+                vec![3.0, 1.0, 2.0, 3.0, 1.0, 2.0, 3.0, 1.0, 2.0, 3.0, 1.0],
+            ),
+            (
+                PadMode::Edge,
+                vec![1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 3.0, 3.0, 3.0, 3.0],
+            ),
+            (
+                PadMode::Constant(7.0),
+                vec![7.0, 7.0, 7.0, 7.0, 1.0, 2.0, 3.0, 7.0, 7.0, 7.0, 7.0],
+            ),
+            (
+                PadMode::Zeros,
+                vec![0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 0.0],
+            ),
+            (PadMode::NoPad, vec![1.0, 2.0, 3.0]),
         ];
+        for (mode, expected) in cases.iter() {
+            assert_eq!(&mode.pad(&data, 4, 4), expected, "{:?}", mode);
+        }
 
-        assert_eq!(data, expected);
+        assert_eq!(PadMode::Reflect.pad(&[5.0f32], 2, 1), vec![5.0; 4]);
+        assert_eq!(
+            PadMode::Symmetric.pad(&[1.0f32, 2.0], 1, 0),
+            vec![1.0, 1.0, 2.0]
+        );
+        assert_eq!(PadMode::Wrap.pad::<f32>(&[], 1, 1), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn unpadded_signal_not_centred() {
+        let stft = StftBuilder::new()
+            .set_fft_num(8)
+            .set_hop_len(2)
+            .set_centred(true)
+            .set_padding_mode(PadMode::NoPad)
+            .build();
+        assert!(!stft.centred);
+
+        let signal = (0..32).map(|x| x as f32).collect::<Vec<_>>();
+        let result = stft.run(&signal).unwrap();
+        assert_eq!(result.ncols(), 13);
+        assert_eq!(stft.istft(&result, None).unwrap().len(), 32);
     }
 
     #[test]
@@ -364,43 +1291,221 @@ mod tests {
             .set_fft_num(5)
             .set_hop_len(1)
             .set_centred(true)
-            .set_windowing_algorithm(WindowingAlgorithm::Hann(5))
+            .set_windowing_algorithm(WindowingAlgorithm::Hann)
             .build();
 
-        let win = stft.get_window_matrix();
         let expected = vec![0.0, 0.3454915, 0.9045085, 0.9045085, 0.3454915];
         let expected = Array::from(expected);
 
-        for (a, e) in win.iter().zip(expected.iter()) {
-            assert!(approx_eq!(f32, a.re, *e));
-            assert!(approx_eq!(f32, a.im, 0.0));
+        for (a, e) in stft.window.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e));
+        }
+
+        let stft = StftBuilder::new()
+            .set_fft_num(6)
+            .set_window_len(4)
+            .set_windowing_algorithm(WindowingAlgorithm::Hamming)
+            .build();
+        let expected = [0.0, 0.08, 0.54, 1.0, 0.54, 0.0];
+        for (a, e) in stft.window.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-6));
+        }
+
+        // Symmetric windows can be requested for filter design
+        let stft = StftBuilder::new()
+            .set_fft_num(4)
+            .set_windowing_algorithm(WindowingAlgorithm::Hann)
+            .set_window_mode(WindowMode::Symmetric)
+            .build();
+        let expected = [0.0, 0.75, 0.75, 0.0];
+        for (a, e) in stft.window.iter().zip(expected.iter()) {
+            assert!(approx_eq!(f32, *a, *e, epsilon = 1e-6));
         }
     }
 
     #[test]
-    fn hann_window() {
-        let hann = WindowingAlgorithm::Hann(5);
-        let expected = vec![0.0, 0.3454915, 0.9045085, 0.9045085, 0.3454915];
+    fn istft_round_trip() {
+        let signal = (0..4000)
+            .map(|x| {
+                let t = x as f32 / 16000.0;
+                (2.0 * PI * 440.0 * t).sin() + 0.5 * (2.0 * PI * 1250.0 * t).cos()
+            })
+            .collect::<Vec<f32>>();
+
+        // (n_fft, win_length, hop_length) as used in the equivalence tests
+        let configs = [
+            (5, 5, 1),
+            (512, 512, 128),
+            (512, 256, 64),
+            (1024, 128, 16),
+            (2048, 2048, 256),
+        ];
+        for (n_fft, win_length, hop_length) in configs.iter() {
+            let stft = StftBuilder::new()
+                .set_fft_num(*n_fft)
+                .set_window_len(*win_length)
+                .set_hop_len(*hop_length)
+                .set_centred(true)
+                .build();
 
-        for i in 0..5 {
-            let res = hann.run(Complex::new(i as f32, 0.0));
-            assert!(approx_eq!(f32, res.re, expected[i]));
-            assert!(approx_eq!(f32, res.im, 0.0));
+            let spectra = stft.run(&signal).unwrap();
+            let recon = stft.istft(&spectra, Some(signal.len())).unwrap();
+            assert_eq!(recon.len(), signal.len());
+            for (a, e) in recon.iter().zip(signal.iter()) {
+                assert!(
+                    approx_eq!(f32, *a, *e, epsilon = 1e-4),
+                    "{} ~= {} for {:?}",
+                    a,
+                    e,
+                    (n_fft, win_length, hop_length)
+                );
+            }
         }
     }
 
+    #[test]
+    fn double_precision() {
+        let signal = (0..4000)
+            .map(|x| (x as f64 * 0.01).sin() + 0.3 * (x as f64 * 0.37).cos())
+            .collect::<Vec<f64>>();
+        let stft = StftBuilder::<f64>::default()
+            .set_fft_num(512)
+            .set_hop_len(128)
+            .build();
+        let single = StftBuilder::new().set_fft_num(512).set_hop_len(128).build();
+
+        let spectra = stft.run(&signal).unwrap();
+        let single_spectra = single.run(&signal).unwrap();
+        assert_eq!(spectra.dim(), single_spectra.dim());
+        for (a, e) in spectra.iter().zip(single_spectra.iter()) {
+            assert!(approx_eq!(f64, a.re, e.re as f64, epsilon = 1e-3));
+            assert!(approx_eq!(f64, a.im, e.im as f64, epsilon = 1e-3));
+        }
+
+        let recon = stft.istft(&spectra, Some(signal.len())).unwrap();
+        for (a, e) in recon.iter().zip(signal.iter()) {
+            assert!(approx_eq!(f64, *a, *e, epsilon = 1e-12), "{} ~= {}", a, e);
+        }
+    }
+
+    #[test]
+    fn istft_lengths() {
+        let stft = StftBuilder::new()
+            .set_fft_num(16)
+            .set_hop_len(4)
+            .set_centred(true)
+            .build();
+        let signal = (0..64).map(|x| (x as f32 * 0.3).sin()).collect::<Vec<_>>();
+        let spectra = stft.run(&signal).unwrap();
+
+        let recon = stft.istft(&spectra, None).unwrap();
+        assert_eq!(recon.len(), signal.len());
+
+        // 17 frames only cover 72 samples after removing the start padding so
+        // anything after that is zero-padded
+        let recon = stft.istft(&spectra, Some(80)).unwrap();
+        assert_eq!(recon.len(), 80);
+        assert!(recon.slice(s![72..]).iter().all(|x| *x == 0.0));
+
+        let recon = stft.istft(&spectra, Some(10)).unwrap();
+        assert_eq!(recon.len(), 10);
+
+        let wrong_shape = Array2::<Complex<f32>>::zeros((5, 3));
+        assert!(matches!(
+            stft.istft(&wrong_shape, None),
+            Err(Error::InvalidParameter(_))
+        ));
+        let no_frames = Array2::<Complex<f32>>::zeros((9, 0));
+        assert!(matches!(
+            stft.istft(&no_frames, None),
+            Err(Error::SignalTooShort { .. })
+        ));
+    }
+
     #[test]
     fn empty_signal() {
         let small = StftBuilder::new()
             .set_fft_num(5)
             .set_hop_len(1)
             .set_centred(true)
-            .set_windowing_algorithm(WindowingAlgorithm::Hann(5))
+            .set_windowing_algorithm(WindowingAlgorithm::Hann)
             .build();
 
         let empty: Vec<f32> = vec![];
         let result = small.run(empty.as_slice());
-        assert_eq!(result, None);
+        assert_eq!(
+            result,
+            Err(Error::SignalTooShort {
+                samples: 0,
+                required: 2
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(
+            StftBuilder::new().set_hop_len(0).try_build(),
+            Err(Error::ZeroHop)
+        );
+        // The default hop of a quarter of the window rounds down to zero
+        assert_eq!(
+            StftBuilder::new().set_fft_num(3).try_build(),
+            Err(Error::ZeroHop)
+        );
+        assert_eq!(
+            StftBuilder::new()
+                .set_fft_num(256)
+                .set_window_len(512)
+                .try_build(),
+            Err(Error::WindowLongerThanFft {
+                win_length: 512,
+                n_fft: 256
+            })
+        );
+        assert!(matches!(
+            StftBuilder::new()
+                .set_window_len(0)
+                .set_hop_len(1)
+                .try_build(),
+            Err(Error::InvalidParameter(_))
+        ));
+        for coefs in [vec![], vec![1.0; 8], vec![1.0; 32]].iter() {
+            assert!(matches!(
+                StftBuilder::new()
+                    .set_fft_num(16)
+                    .set_windowing_algorithm(WindowingAlgorithm::Custom(coefs.clone()))
+                    .try_build(),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+        assert!(StftBuilder::new()
+            .set_fft_num(16)
+            .set_window_len(8)
+            .set_windowing_algorithm(WindowingAlgorithm::Custom(vec![1.0; 8]))
+            .try_build()
+            .is_ok());
+
+        let stft = StftBuilder::new()
+            .set_fft_num(16)
+            .set_padding_mode(PadMode::NoPad)
+            .try_build()
+            .unwrap();
+        assert_eq!(stft.min_samples(), 16);
+        assert_eq!(
+            stft.run(&[0.0f32; 15]),
+            Err(Error::SignalTooShort {
+                samples: 15,
+                required: 16
+            })
+        );
+        assert!(stft.run(&[0.0f32; 16]).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn build_panics_on_invalid_parameters() {
+        StftBuilder::new().set_hop_len(0).build();
     }
 
     #[test]
@@ -409,15 +1514,17 @@ mod tests {
             .set_fft_num(5)
             .set_hop_len(1)
             .set_centred(true)
-            .set_windowing_algorithm(WindowingAlgorithm::Hann(5))
+            .set_padding_mode(PadMode::Reflect)
+            .set_windowing_algorithm(WindowingAlgorithm::Hann)
             .build();
 
         let result = small.run(&(1..20).collect::<Vec<_>>()).unwrap();
         assert_eq!(result.shape(), &[3, 19]);
         // Generated via
-        // librosa.stft(np.arange(1.0, 20.0), n_fft=5, hop_length=1, win_length=5, window='hann')
+        // librosa.stft(np.arange(1.0, 20.0), n_fft=5, hop_length=1, win_length=5, window='hann',
+        //              pad_mode='reflect')
         let expected = vec![
-            Complex::new(4.4409828, 0.0),
+            Complex::new(4.440_983, 0.0),
             Complex::new(6.25, 0.0),
             Complex::new(8.75, 0.0),
             Complex::new(11.25, 0.0),
@@ -459,14 +1566,14 @@ mod tests {
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(-4.44089e-16, -0.25101426),
-            Complex::new(4.4408921e-16, -0.25101426),
+            Complex::new(4.440_892e-16, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
-            Complex::new(-8.8817842e-16, -0.25101426),
-            Complex::new(-8.8817842e-16, -0.25101426),
+            Complex::new(-8.881_784e-16, -0.25101426),
+            Complex::new(-8.881_784e-16, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
             Complex::new(0.0, -0.25101426),
@@ -0,0 +1,286 @@
+use ndarray::prelude::*;
+use num_traits::Float;
+use std::f64::consts::PI;
+
+/// Whether a window is generated for filter design or spectral analysis. This
+/// matches the `fftbins` argument of `scipy.signal.get_window`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WindowMode {
+    /// The window is symmetric about its centre, `fftbins=False` in scipy
+    Symmetric,
+    /// The window is one period of a periodic function, `fftbins=True` in
+    /// scipy. This is a symmetric window one sample longer with the last
+    /// sample dropped and is what librosa uses for the STFT
+    Periodic,
+}
+
+/// Windowing algorithm to be applied to the signal. These mirror the windows
+/// provided by `scipy.signal.get_window`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum WindowingAlgorithm {
+    /// Hann window, a raised cosine with coefficients 0.5 and 0.5
+    #[default]
+    Hann,
+    /// Hamming window, a raised cosine with coefficients 0.54 and 0.46
+    Hamming,
+    /// Three term Blackman window
+    Blackman,
+    /// Four term minimum Blackman-Harris window
+    BlackmanHarris,
+    /// Triangular window with zeros at both ends
+    Bartlett,
+    /// Rectangular window, otherwise known as a boxcar
+    Rectangular,
+    /// Kaiser window with the given beta shape parameter
+    Kaiser(f32),
+    /// Gaussian window with the given standard deviation in samples
+    Gaussian(f32),
+    /// Tukey (tapered cosine) window with the given fraction of the window
+    /// inside the cosine tapered region
+    Tukey(f32),
+    /// The window used by Kaldi, a Hann window raised to the power of 0.85
+    Povey,
+    /// User supplied window coefficients. These are used as is regardless of
+    /// the window mode, being truncated or zero padded to the requested length.
+    /// The STFT rejects custom windows that aren't exactly the window length
+    Custom(Vec<f32>),
+}
+
+impl WindowingAlgorithm {
+    /// Generate a window of `len` samples. The window is evaluated in double
+    /// precision and converted to the requested float type
+    pub fn generate<F: Float>(&self, len: usize, mode: WindowMode) -> Array1<F> {
+        if let Self::Custom(coefs) = self {
+            let mut window = Array1::zeros(len);
+            let n = len.min(coefs.len());
+            window.slice_mut(s![..n]).assign(
+                &coefs[..n]
+                    .iter()
+                    .map(|x| F::from(*x).unwrap())
+                    .collect::<Array1<F>>(),
+            );
+            return window;
+        }
+        if len <= 1 {
+            // scipy returns ones for trivial windows in either mode
+            return Array1::ones(len);
+        }
+        let m = match mode {
+            WindowMode::Symmetric => len,
+            WindowMode::Periodic => len + 1,
+        };
+        Array1::from_shape_fn(len, |n| F::from(self.evaluate(n as f64, m)).unwrap())
+    }
+
+    /// Evaluate sample `n` of the symmetric window of length `m`
+    fn evaluate(&self, n: f64, m: usize) -> f64 {
+        if m <= 1 {
+            return 1.0;
+        }
+        let denom = (m - 1) as f64;
+        // Phase running from -pi to pi across the window for cosine sums
+        let general_cosine = |coefs: &[f64]| {
+            let fac = -PI + 2.0 * PI * n / denom;
+            coefs
+                .iter()
+                .enumerate()
+                .fold(0.0, |acc, (k, a)| acc + a * (k as f64 * fac).cos())
+        };
+        match self {
+            Self::Hann => general_cosine(&[0.5, 0.5]),
+            Self::Hamming => general_cosine(&[0.54, 0.46]),
+            Self::Blackman => general_cosine(&[0.42, 0.5, 0.08]),
+            Self::BlackmanHarris => general_cosine(&[0.35875, 0.48829, 0.14128, 0.01168]),
+            Self::Bartlett => {
+                if n <= denom / 2.0 {
+                    2.0 * n / denom
+                } else {
+                    2.0 - 2.0 * n / denom
+                }
+            }
+            Self::Rectangular => 1.0,
+            Self::Kaiser(beta) => {
+                let alpha = denom / 2.0;
+                let ratio = (n - alpha) / alpha;
+                let beta = *beta as f64;
+                let x = beta * (1.0 - ratio * ratio).max(0.0).sqrt();
+                bessel_i0(x) / bessel_i0(beta)
+            }
+            Self::Gaussian(std) => {
+                let x = n - denom / 2.0;
+                let std = *std as f64;
+                (-(x * x) / (2.0 * std * std)).exp()
+            }
+            Self::Tukey(alpha) => {
+                let alpha = *alpha as f64;
+                if alpha <= 0.0 {
+                    1.0
+                } else if alpha >= 1.0 {
+                    general_cosine(&[0.5, 0.5])
+                } else {
+                    let width = (alpha * denom / 2.0).floor();
+                    if n <= width {
+                        0.5 * (1.0 + (PI * (-1.0 + 2.0 * n / alpha / denom)).cos())
+                    } else if n >= denom - width {
+                        0.5 * (1.0 + (PI * (-2.0 / alpha + 1.0 + 2.0 * n / alpha / denom)).cos())
+                    } else {
+                        1.0
+                    }
+                }
+            }
+            Self::Povey => (0.5 - 0.5 * (2.0 * PI * n / denom).cos()).powf(0.85),
+            Self::Custom(_) => unreachable!("Custom windows aren't evaluated"),
+        }
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind, used by the
+/// Kaiser window
+pub(crate) fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-17 {
+        term *= (half / k) * (half / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn check_window(window: &WindowingAlgorithm, mode: WindowMode, expected: &[f32]) {
+        let result = window.generate(expected.len(), mode);
+        assert_eq!(result.len(), expected.len());
+        for (a, e) in result.iter().zip(expected.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = 1e-6),
+                "{:?} {:?}: {} ~= {}",
+                window,
+                mode,
+                a,
+                e
+            );
+        }
+    }
+
+    #[test]
+    fn hann_window() {
+        // Generated via scipy.signal.get_window('hann', N, fftbins=...)
+        let hann = WindowingAlgorithm::Hann;
+        check_window(&hann, WindowMode::Periodic, &[0.0, 0.5, 1.0, 0.5]);
+        check_window(&hann, WindowMode::Symmetric, &[0.0, 0.75, 0.75, 0.0]);
+        check_window(
+            &hann,
+            WindowMode::Periodic,
+            &[0.0, 0.345_491_5, 0.904_508_5, 0.904_508_5, 0.345_491_5],
+        );
+        check_window(&hann, WindowMode::Symmetric, &[0.0, 0.5, 1.0, 0.5, 0.0]);
+        check_window(
+            &hann,
+            WindowMode::Periodic,
+            &[
+                0.0,
+                0.146_446_6,
+                0.5,
+                0.853_553_4,
+                1.0,
+                0.853_553_4,
+                0.5,
+                0.146_446_6,
+            ],
+        );
+        check_window(&hann, WindowMode::Periodic, &[1.0]);
+        check_window(&hann, WindowMode::Symmetric, &[1.0]);
+        assert!(hann.generate::<f32>(0, WindowMode::Periodic).is_empty());
+    }
+
+    #[test]
+    fn symmetric_windows() {
+        // Generated via scipy.signal.get_window(window, N, fftbins=False)
+        let cases = vec![
+            (
+                WindowingAlgorithm::Hamming,
+                vec![0.08, 0.54, 1.0, 0.54, 0.08],
+            ),
+            (
+                WindowingAlgorithm::Blackman,
+                vec![0.0, 0.200_770_1, 0.849_229_9, 0.849_229_9, 0.200_770_1, 0.0],
+            ),
+            (
+                WindowingAlgorithm::BlackmanHarris,
+                vec![
+                    6.0e-05,
+                    0.103_011_5,
+                    0.793_833_5,
+                    0.793_833_5,
+                    0.103_011_5,
+                    6.0e-05,
+                ],
+            ),
+            (WindowingAlgorithm::Bartlett, vec![0.0, 0.5, 1.0, 0.5, 0.0]),
+            (WindowingAlgorithm::Rectangular, vec![1.0, 1.0, 1.0]),
+            (
+                WindowingAlgorithm::Kaiser(8.6),
+                vec![
+                    0.001_332_514,
+                    0.201_054_9,
+                    0.849_416_2,
+                    0.849_416_2,
+                    0.201_054_9,
+                    0.001_332_514,
+                ],
+            ),
+            (
+                WindowingAlgorithm::Gaussian(1.0),
+                vec![0.135_335_28, 0.606_530_66, 1.0, 0.606_530_66, 0.135_335_28],
+            ),
+            (
+                WindowingAlgorithm::Tukey(0.5),
+                vec![0.0, 1.0, 1.0, 1.0, 0.0],
+            ),
+            (WindowingAlgorithm::Tukey(1.0), vec![0.0, 0.75, 0.75, 0.0]),
+            (WindowingAlgorithm::Tukey(0.0), vec![1.0, 1.0, 1.0, 1.0]),
+            (
+                WindowingAlgorithm::Povey,
+                vec![0.0, 0.554_784_7, 1.0, 0.554_784_7, 0.0],
+            ),
+            (WindowingAlgorithm::Hamming, vec![1.0]),
+        ];
+
+        for (window, expected) in cases.iter() {
+            check_window(window, WindowMode::Symmetric, expected);
+        }
+    }
+
+    #[test]
+    fn periodic_windows() {
+        // Generated via scipy.signal.get_window(window, N, fftbins=True)
+        check_window(
+            &WindowingAlgorithm::Hamming,
+            WindowMode::Periodic,
+            &[0.08, 0.54, 1.0, 0.54],
+        );
+        check_window(
+            &WindowingAlgorithm::Bartlett,
+            WindowMode::Periodic,
+            &[0.0, 0.5, 1.0, 0.5],
+        );
+        check_window(
+            &WindowingAlgorithm::Blackman,
+            WindowMode::Periodic,
+            &[0.0, 0.34, 1.0, 0.34],
+        );
+
+        let custom = WindowingAlgorithm::Custom(vec![0.1, 0.2, 0.3]);
+        check_window(&custom, WindowMode::Periodic, &[0.1, 0.2, 0.3]);
+        check_window(&custom, WindowMode::Symmetric, &[0.1, 0.2, 0.3]);
+        check_window(&custom, WindowMode::Periodic, &[0.1, 0.2]);
+        check_window(&custom, WindowMode::Periodic, &[0.1, 0.2, 0.3, 0.0]);
+    }
+}
@@ -23,7 +23,7 @@ fn check_data_folder() -> PathBuf {
         Command::new("python")
             .arg("init_data_dir.py")
             .arg(format!("-f={}", data_dir.display()))
-            .arg("-s 10")
+            .args(["-s", "10"])
             .output()
             .expect("Failed to create test data");
     }
@@ -39,6 +39,29 @@ fn stft_from_params(params: ArrayView1<f32>) -> ShortTimeFourierTransform {
         .build()
 }
 
+fn mel_filterbank_from_params(
+    params: ArrayView1<f32>,
+    mel_params: ArrayView1<f32>,
+) -> MelFilterbank {
+    let scale = if mel_params[4] > 0.5 {
+        MelScale::Htk
+    } else {
+        MelScale::Slaney
+    };
+    let norm = if mel_params[5] > 0.5 {
+        FilterbankNorm::Slaney
+    } else {
+        FilterbankNorm::None
+    };
+    MelFilterbankBuilder::new(mel_params[0], params[0] as usize)
+        .set_mel_num(mel_params[1] as usize)
+        .set_fmin(mel_params[2])
+        .set_fmax(mel_params[3])
+        .set_mel_scale(scale)
+        .set_norm(norm)
+        .build()
+}
+
 #[test]
 fn stft_equivalence() {
     let data_dir = check_data_folder();
@@ -75,6 +98,45 @@ fn stft_equivalence() {
     }
 }
 
+#[test]
+fn stft_f64_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let result: Array2<f64> = npz.by_name("stft.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+
+        if !result.is_empty() {
+            let stft = StftBuilder::<f64>::default()
+                .set_fft_num(params[0] as usize)
+                .set_window_len(params[1] as usize)
+                .set_hop_len(params[2] as usize)
+                .set_centred(true)
+                .build();
+            // In double precision the results should be much closer to librosa
+            let max_possible_bin_value = samples.iter().fold(0.0, |acc, x| acc + x.powi(2));
+            let eps = max_possible_bin_value * 1e-10;
+            let stft_res = stft.run(samples.as_slice().unwrap()).unwrap().mag();
+            assert_eq!(stft_res.dim(), result.dim());
+            for (a, e) in stft_res.iter().zip(result.iter()) {
+                assert!(
+                    approx_eq!(f64, *a, *e, epsilon = eps),
+                    "{} ~= {} (+/- {})",
+                    a,
+                    e,
+                    eps
+                );
+            }
+        }
+    }
+}
+
 #[test]
 fn spectrogram_equivalence() {
     let data_dir = check_data_folder();
@@ -95,7 +157,7 @@ fn spectrogram_equivalence() {
             let eps = (max_possible_bin_value.powf(params[3] as f64) * 2e-4) as f32;
 
             let stft = stft_from_params(params.view());
-            let spectra = samples.spectrum(stft, Some(params[3])).unwrap();
+            let spectra = samples.spectrum(&stft, Some(params[3])).unwrap();
             assert_eq!(spectra.dim(), result.dim());
             for (a, e) in spectra.iter().zip(result.iter()) {
                 assert!(
@@ -109,3 +171,578 @@ fn spectrogram_equivalence() {
         }
     }
 }
+
+#[test]
+fn mel_filterbank_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let result: Array2<f32> = npz.by_name("mel_filterbank.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+
+        let odd_result: Array2<f32> = npz.by_name("odd_mel_filterbank.npy").unwrap();
+        let mut odd_params = params.clone();
+        odd_params[0] -= 1.0;
+
+        for (params, result) in [(params, result), (odd_params, odd_result)].iter() {
+            let filterbank = mel_filterbank_from_params(params.view(), mel_params.view());
+            assert_eq!(filterbank.weights().dim(), result.dim());
+            for (a, e) in filterbank.weights().iter().zip(result.iter()) {
+                assert!(
+                    approx_eq!(f32, *a, *e, epsilon = 1e-6),
+                    "{} ~= {} (+/- {}) for n_fft {}",
+                    a,
+                    e,
+                    1e-6,
+                    params[0]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn melspectrogram_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let result: Array2<f64> = npz.by_name("melspectrogram.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let result = result.mapv(|x| x as f32);
+
+        if !result.is_empty() {
+            let max_possible_bin_value = samples.iter().fold(0.0, |acc, x| acc + x.powi(2));
+            let eps = (max_possible_bin_value.powf(params[3] as f64) * 2e-4) as f32;
+
+            let stft = stft_from_params(params.view());
+            let filterbank = mel_filterbank_from_params(params.view(), mel_params.view());
+            let mel = samples
+                .melspectrogram(&stft, &filterbank, Some(params[3]))
+                .unwrap();
+            assert_eq!(mel.dim(), result.dim());
+            for (a, e) in mel.iter().zip(result.iter()) {
+                assert!(
+                    approx_eq!(f32, *a, *e, epsilon = eps),
+                    "{} ~= {} (+/- {})",
+                    a,
+                    e,
+                    eps
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn librosa_mfcc_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let result: Array2<f64> = npz.by_name("librosa_mfcc.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let mfcc_params: Array1<f32> = npz.by_name("mfcc_params.npy").unwrap();
+        let result = result.mapv(|x| x as f32);
+
+        let mfcc = MfccBuilder::librosa(mel_params[0])
+            .set_mfcc_num(mfcc_params[0] as usize)
+            .set_lifter(mfcc_params[1] as usize)
+            .set_fft_num(params[0] as usize)
+            .set_window_len(params[1] as usize)
+            .set_hop_len(params[2] as usize)
+            .set_mel_num(mel_params[1] as usize)
+            .set_fmin(mel_params[2])
+            .set_fmax(mel_params[3])
+            .build();
+        let cepstra = mfcc.run(samples.as_slice().unwrap()).unwrap();
+
+        // Coefficients are in decibels so use a tolerance relative to the peak
+        let eps = result.fold(0.0f32, |acc, x| acc.max(x.abs())) * 5e-3;
+        assert_eq!(cepstra.dim(), result.dim());
+        for (a, e) in cepstra.iter().zip(result.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = eps),
+                "{} ~= {} (+/- {})",
+                a,
+                e,
+                eps
+            );
+        }
+    }
+}
+
+#[test]
+fn speech_features_mfcc_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let result: Array2<f64> = npz.by_name("psf_mfcc.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let mfcc_params: Array1<f32> = npz.by_name("mfcc_params.npy").unwrap();
+        // python_speech_features returns (frames, coefficients)
+        let result = result.mapv(|x| x as f32).reversed_axes();
+
+        let mfcc = MfccBuilder::python_speech_features(mel_params[0])
+            .set_mfcc_num(mfcc_params[0] as usize)
+            .set_lifter(mfcc_params[1] as usize)
+            .set_fft_num(mfcc_params[2] as usize)
+            .set_mel_num(mel_params[1] as usize)
+            .set_fmin(mel_params[2])
+            .set_fmax(mel_params[3])
+            .build();
+        let cepstra = mfcc.run(samples.as_slice().unwrap()).unwrap();
+
+        let eps = result.fold(0.0f32, |acc, x| acc.max(x.abs())) * 5e-3;
+        assert_eq!(cepstra.dim(), result.dim());
+        for (a, e) in cepstra.iter().zip(result.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = eps),
+                "{} ~= {} (+/- {})",
+                a,
+                e,
+                eps
+            );
+        }
+    }
+}
+
+fn assert_all_close(result: &Array2<f64>, expected: &Array2<f64>, eps: f64) {
+    assert_eq!(result.dim(), expected.dim());
+    for (a, e) in result.iter().zip(expected.iter()) {
+        assert!(
+            approx_eq!(f64, *a, *e, epsilon = eps),
+            "{} ~= {} (+/- {})",
+            a,
+            e,
+            eps
+        );
+    }
+}
+
+#[test]
+fn decibel_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let melspectrogram: Array2<f64> = npz.by_name("melspectrogram.npy").unwrap();
+        let magnitude: Array2<f64> = npz.by_name("stft.npy").unwrap();
+        let db_params: Array1<f64> = npz.by_name("db_params.npy").unwrap();
+        let power_db: Array2<f64> = npz.by_name("power_db.npy").unwrap();
+        let amplitude_db: Array2<f64> = npz.by_name("amplitude_db.npy").unwrap();
+        let db_power: Array2<f64> = npz.by_name("db_power.npy").unwrap();
+        let db_amplitude: Array2<f64> = npz.by_name("db_amplitude.npy").unwrap();
+
+        let reference = if db_params[0] > 0.5 {
+            DbReference::Max
+        } else {
+            DbReference::Value(db_params[1])
+        };
+        let top_db = if db_params[3] >= 0.0 {
+            Some(db_params[3])
+        } else {
+            None
+        };
+
+        // Starting from the librosa spectrograms in double precision these
+        // should be almost exact
+        let result = power_to_db(&melspectrogram, reference, db_params[2], top_db).unwrap();
+        assert_all_close(&result, &power_db, 1e-8);
+        let result = amplitude_to_db(&magnitude, reference, db_params[2], top_db).unwrap();
+        assert_all_close(&result, &amplitude_db, 1e-8);
+
+        let result = db_to_power(&power_db, db_params[1]);
+        let eps = db_power.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-12;
+        assert_all_close(&result, &db_power, eps);
+        let result = db_to_amplitude(&amplitude_db, db_params[1]);
+        let eps = db_amplitude.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-12;
+        assert_all_close(&result, &db_amplitude, eps);
+    }
+}
+
+#[test]
+fn spectral_features_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let magnitude: Array2<f64> = npz.by_name("stft.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let centroid: Array2<f64> = npz.by_name("spectral_centroid.npy").unwrap();
+        let bandwidth: Array2<f64> = npz.by_name("spectral_bandwidth.npy").unwrap();
+        let rolloff: Array2<f64> = npz.by_name("spectral_rolloff.npy").unwrap();
+        let flatness: Array2<f64> = npz.by_name("spectral_flatness.npy").unwrap();
+        let contrast: Array2<f64> = npz.by_name("spectral_contrast.npy").unwrap();
+        let sr = mel_params[0] as f64;
+        let n_fft = params[0] as usize;
+
+        let result = magnitude
+            .spectral_centroid(sr, n_fft)
+            .unwrap()
+            .insert_axis(Axis(0));
+        assert_all_close(&result, &centroid, 1e-6);
+        let result = magnitude
+            .spectral_bandwidth(sr, n_fft, 2.0)
+            .unwrap()
+            .insert_axis(Axis(0));
+        assert_all_close(&result, &bandwidth, 1e-6);
+        let result = magnitude
+            .spectral_rolloff(sr, n_fft, 0.85)
+            .unwrap()
+            .insert_axis(Axis(0));
+        assert_all_close(&result, &rolloff, 1e-6);
+        let result = magnitude
+            .spectral_flatness(2.0, 1e-10)
+            .unwrap()
+            .insert_axis(Axis(0));
+        assert_all_close(&result, &flatness, 1e-8);
+        let result = magnitude
+            .spectral_contrast(sr, n_fft, 200.0, 4, 0.02, false)
+            .unwrap();
+        assert_all_close(&result, &contrast, 1e-6);
+    }
+}
+
+#[test]
+fn speech_features_ssc_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let result: Array2<f64> = npz.by_name("psf_ssc.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let mfcc_params: Array1<f32> = npz.by_name("mfcc_params.npy").unwrap();
+        // python_speech_features returns (frames, filters)
+        let result = result.mapv(|x| x as f32).reversed_axes();
+
+        let features = SpeechFeaturesBuilder::new(mel_params[0])
+            .set_filter_num(mel_params[1] as usize)
+            .set_fft_num(mfcc_params[2] as usize)
+            .set_lowfreq(mel_params[2])
+            .set_highfreq(mel_params[3])
+            .build();
+        let ssc = features.ssc(samples.as_slice().unwrap()).unwrap();
+
+        // Centroids are frequencies so allow a fraction of a Hz of error. Too
+        // many filters for the FFT size leaves some empty giving NaNs in both
+        assert_eq!(ssc.dim(), result.dim());
+        for (a, e) in ssc.iter().zip(result.iter()) {
+            assert!(
+                (a.is_nan() && e.is_nan()) || approx_eq!(f32, *a, *e, epsilon = 0.5),
+                "{} ~= {} (+/- 0.5)",
+                a,
+                e
+            );
+        }
+    }
+}
+
+#[test]
+fn speech_features_fbank_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let fbank: Array2<f64> = npz.by_name("psf_fbank.npy").unwrap();
+        let energy: Array1<f64> = npz.by_name("psf_energy.npy").unwrap();
+        let logfbank: Array2<f64> = npz.by_name("psf_logfbank.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let mfcc_params: Array1<f32> = npz.by_name("mfcc_params.npy").unwrap();
+
+        let features = SpeechFeaturesBuilder::new(mel_params[0])
+            .set_filter_num(mel_params[1] as usize)
+            .set_fft_num(mfcc_params[2] as usize)
+            .set_lowfreq(mel_params[2])
+            .set_highfreq(mel_params[3])
+            .set_window(WindowingAlgorithm::Hamming)
+            .build();
+        let samples = samples.as_slice().unwrap();
+        let (feat, total) = features.fbank(samples).unwrap();
+        let log = features.logfbank(samples).unwrap();
+
+        // python_speech_features returns (frames, filters)
+        let fbank = fbank.reversed_axes();
+        let logfbank = logfbank.reversed_axes();
+        let eps = fbank.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-5;
+        assert_all_close(&feat.mapv(|x| x as f64), &fbank, eps);
+        let eps = energy.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-5;
+        assert_all_close(
+            &total.mapv(|x| x as f64).insert_axis(Axis(0)),
+            &energy.insert_axis(Axis(0)),
+            eps,
+        );
+        // Empty filters are floored at epsilon in both so the logs match
+        assert_all_close(&log.mapv(|x| x as f64), &logfbank, 1e-3);
+
+        // Frames longer than the FFT are truncated
+        let fbank: Array2<f64> = npz.by_name("psf_truncated_fbank.npy").unwrap();
+        let energy: Array1<f64> = npz.by_name("psf_truncated_energy.npy").unwrap();
+        let features = SpeechFeaturesBuilder::new(mel_params[0])
+            .set_filter_num(mel_params[1] as usize)
+            .set_fft_num(128)
+            .set_lowfreq(mel_params[2])
+            .set_highfreq(mel_params[3])
+            .set_window(WindowingAlgorithm::Hamming)
+            .build();
+        let (feat, total) = features.fbank(samples).unwrap();
+        let fbank = fbank.reversed_axes();
+        let eps = fbank.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-5;
+        assert_all_close(&feat.mapv(|x| x as f64), &fbank, eps);
+        let eps = energy.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-5;
+        assert_all_close(
+            &total.mapv(|x| x as f64).insert_axis(Axis(0)),
+            &energy.insert_axis(Axis(0)),
+            eps,
+        );
+    }
+}
+
+#[test]
+fn kaldi_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let mel_params: Array1<f32> = npz.by_name("mel_params.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("kaldi_params.npy").unwrap();
+        let fbank: Array2<f32> = npz.by_name("kaldi_fbank.npy").unwrap();
+        let mfcc: Array2<f32> = npz.by_name("kaldi_mfcc.npy").unwrap();
+
+        let builder = KaldiFeaturesBuilder::new(mel_params[0])
+            .set_frame_length(params[0])
+            .set_frame_shift(params[1])
+            .set_snip_edges(params[2] > 0.5)
+            .set_preemphasis(params[3])
+            .set_remove_dc_offset(params[4] > 0.5)
+            .set_raw_energy(params[5] > 0.5)
+            .set_use_energy(params[6] > 0.5)
+            .set_low_freq(params[7])
+            .set_high_freq(params[8])
+            .set_mel_num(params[9] as usize);
+        let samples = samples.mapv(|x| x as f32);
+
+        // torchaudio returns (frames, features)
+        let features = builder.clone().build();
+        let result = features.fbank(samples.as_slice().unwrap()).unwrap();
+        let expected = fbank.reversed_axes();
+        let eps = expected.fold(0.0f32, |acc, x| acc.max(x.abs())) * 1e-3;
+        assert_eq!(result.dim(), expected.dim());
+        for (a, e) in result.iter().zip(expected.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = eps),
+                "{} ~= {} (+/- {})",
+                a,
+                e,
+                eps
+            );
+        }
+
+        let features = builder.set_ceps_num(params[10] as usize).build();
+        let result = features.mfcc(samples.as_slice().unwrap()).unwrap();
+        let expected = mfcc.reversed_axes();
+        let eps = expected.fold(0.0f32, |acc, x| acc.max(x.abs())) * 5e-3;
+        assert_eq!(result.dim(), expected.dim());
+        for (a, e) in result.iter().zip(expected.iter()) {
+            assert!(
+                approx_eq!(f32, *a, *e, epsilon = eps),
+                "{} ~= {} (+/- {})",
+                a,
+                e,
+                eps
+            );
+        }
+    }
+}
+
+#[test]
+fn delta_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let librosa_mfcc: Array2<f64> = npz.by_name("librosa_mfcc.npy").unwrap();
+        let psf_mfcc: Array2<f64> = npz.by_name("psf_mfcc.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("delta_params.npy").unwrap();
+        let librosa_delta: Array2<f64> = npz.by_name("librosa_delta.npy").unwrap();
+        let psf_delta: Array2<f64> = npz.by_name("psf_delta.npy").unwrap();
+
+        let mode = match params[2] as usize {
+            0 => DeltaMode::Interp,
+            1 => DeltaMode::Mirror,
+            2 => DeltaMode::Constant(0.0),
+            3 => DeltaMode::Nearest,
+            _ => DeltaMode::Wrap,
+        };
+        // Starting from the same features in double precision these should be
+        // almost exact
+        let result = librosa_mfcc
+            .delta(params[0] as usize, params[1] as usize, Axis(1), mode)
+            .unwrap();
+        let eps = librosa_delta.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-9;
+        assert_all_close(&result, &librosa_delta, eps);
+
+        // python_speech_features returns (frames, coefficients)
+        let result = psf_mfcc
+            .regression_delta(params[3] as usize, Axis(0))
+            .unwrap();
+        let eps = psf_delta.fold(0.0f64, |acc, x| acc.max(x.abs())) * 1e-9;
+        assert_all_close(&result, &psf_delta, eps);
+    }
+}
+
+#[test]
+fn resample_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("resample_params.npy").unwrap();
+        let expected: Array1<f64> = npz.by_name("resampled.npy").unwrap();
+
+        let filter = if params[2] > 0.5 {
+            ResampleFilter::KaiserBest
+        } else {
+            ResampleFilter::KaiserFast
+        };
+        let resampler = ResamplerBuilder::<f64>::with_rates(params[0], params[1])
+            .set_filter(filter)
+            .build();
+        let result = resampler.run(samples.as_slice().unwrap()).unwrap();
+        assert_all_close(
+            &result.insert_axis(Axis(0)),
+            &expected.insert_axis(Axis(0)),
+            1e-6,
+        );
+    }
+}
+
+#[test]
+fn framewise_metrics_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let samples: Array1<f64> = npz.by_name("audio.npy").unwrap();
+        let params: Array1<f32> = npz.by_name("params.npy").unwrap();
+        let stft: Array2<f64> = npz.by_name("stft.npy").unwrap();
+        let expected_rms: Array2<f64> = npz.by_name("rms.npy").unwrap();
+        let expected_spectrogram_rms: Array2<f64> = npz.by_name("spectrogram_rms.npy").unwrap();
+        let expected_zcr: Array2<f64> = npz.by_name("zcr.npy").unwrap();
+
+        let samples = samples.as_slice().unwrap();
+        let frame_length = params[0] as usize;
+        let hop_length = params[2] as usize;
+
+        let rms = frame_rms(samples, frame_length, hop_length, PadMode::Zeros).unwrap();
+        assert_all_close(&rms.insert_axis(Axis(0)), &expected_rms, 1e-6);
+
+        let rms = spectrogram_rms(&stft, frame_length).unwrap();
+        assert_all_close(&rms.insert_axis(Axis(0)), &expected_spectrogram_rms, 1e-6);
+
+        let shifted = samples.iter().map(|x| x - 0.5).collect::<Vec<_>>();
+        let zcr = zero_crossing_rate(&shifted, frame_length, hop_length, PadMode::Edge).unwrap();
+        assert_all_close(&zcr.insert_axis(Axis(0)), &expected_zcr, 1e-9);
+    }
+}
+
+#[test]
+fn bss_eval_equivalence() {
+    let data_dir = check_data_folder();
+    for entry in read_dir(&data_dir).unwrap() {
+        let entry = entry.unwrap();
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        let mut npz = NpzReader::new(File::open(entry.path()).unwrap()).unwrap();
+        let references: Array2<f64> = npz.by_name("bss_references.npy").unwrap();
+        let estimates: Array2<f64> = npz.by_name("bss_estimates.npy").unwrap();
+        let sdr: Array1<f64> = npz.by_name("bss_sdr.npy").unwrap();
+        let sir: Array1<f64> = npz.by_name("bss_sir.npy").unwrap();
+        let sar: Array1<f64> = npz.by_name("bss_sar.npy").unwrap();
+
+        // Only the first source is checked as the 1024x1024 system is slow to
+        // solve in debug builds
+        let result = bss_eval(&references, estimates.row(0), 0, 512).unwrap();
+        assert!(
+            approx_eq!(f64, result.sdr, sdr[0], epsilon = 1e-4),
+            "{:?} {}",
+            result,
+            sdr[0]
+        );
+        assert!(
+            approx_eq!(f64, result.sir, sir[0], epsilon = 1e-4),
+            "{:?} {}",
+            result,
+            sir[0]
+        );
+        assert!(
+            approx_eq!(f64, result.sar, sar[0], epsilon = 1e-4),
+            "{:?} {}",
+            result,
+            sar[0]
+        );
+    }
+}
@@ -1,9 +1,14 @@
 import argparse
 import librosa
+import mir_eval
+import python_speech_features as psf
+import resampy
 from librosa.core import spectrum
 import numpy.random as npr
 import numpy as np
 import os
+import torch
+import torchaudio.compliance.kaldi as kaldi
 
 def get_signal_length():
     # Assuming 16KHz sample rate this would be 10ms -> 1s audio sample
@@ -26,6 +31,59 @@ def get_stft_params():
     power = npr.choice([1.0, 2.0])
     return np.array([nfft, win_length, hop_length, power], dtype='float32')
 
+def get_mel_params(nfft):
+    sr = npr.choice([8000, 16000, 22050])
+    n_mels = npr.choice([20, 40, 64, 80])
+    fmin = npr.choice([0.0, 20.0, 133.33])
+    fmax = npr.choice([sr/2, sr/2 - 500.0])
+    htk = npr.choice([0.0, 1.0])
+    slaney_norm = npr.choice([0.0, 1.0])
+    return np.array([sr, n_mels, fmin, fmax, htk, slaney_norm], dtype='float32')
+
+def get_mfcc_params():
+    n_mfcc = npr.choice([13, 20])
+    lifter = npr.choice([0, 22])
+    psf_nfft = npr.choice([512, 1024])
+    return np.array([n_mfcc, lifter, psf_nfft], dtype='float32')
+
+def get_db_params():
+    use_max = npr.choice([0.0, 1.0])
+    ref = npr.choice([1.0, 0.5, 10.0])
+    amin = npr.choice([1e-10, 1e-5])
+    # Negative values mean no top_db clipping
+    top_db = npr.choice([80.0, 40.0, -1.0])
+    return np.array([use_max, ref, amin, top_db], dtype='float64')
+
+def get_kaldi_params():
+    frame_length = npr.choice([25.0, 32.0])
+    frame_shift = npr.choice([10.0, 12.5])
+    snip_edges = npr.choice([0.0, 1.0])
+    preemphasis = npr.choice([0.0, 0.97])
+    remove_dc_offset = npr.choice([0.0, 1.0])
+    raw_energy = npr.choice([0.0, 1.0])
+    use_energy = npr.choice([0.0, 1.0])
+    low_freq = npr.choice([20.0, 64.0])
+    # Non-positive values are relative to the nyquist frequency
+    high_freq = npr.choice([0.0, -400.0])
+    num_mel_bins = npr.choice([23, 40])
+    num_ceps = npr.choice([13, 20])
+    return np.array([frame_length, frame_shift, snip_edges, preemphasis, remove_dc_offset,
+        raw_energy, use_energy, low_freq, high_freq, num_mel_bins, num_ceps], dtype='float32')
+
+def get_delta_params():
+    width = npr.choice([3, 5, 9])
+    order = npr.choice([1, 2])
+    # Index into ['interp', 'mirror', 'constant', 'nearest', 'wrap']
+    mode = npr.randint(5)
+    N = npr.choice([1, 2, 3])
+    return np.array([width, order, mode, N], dtype='float32')
+
+def get_resample_params(sr):
+    target_sr = npr.choice([8000, 16000, 22050, 44100, 48000])
+    # 0 for kaiser_fast and 1 for kaiser_best
+    best = npr.choice([0.0, 1.0])
+    return np.array([sr, target_sr, best], dtype='float32')
+
 def generate_audio():
     return npr.rand(get_signal_length())
 
@@ -34,18 +92,126 @@ def generate_spectrum_data(filename):
     params = get_stft_params()
     stft = librosa.stft(y=audio, n_fft=int(params[0]),
             win_length=int(params[1]), hop_length=int(params[2]),
-            window='hann', center=True, pad_mode='reflect')
+            window='hann', center=True, pad_mode='constant')
     stft, _ = librosa.magphase(stft)
     if stft.ndim == 1:
         stft = np.expand_dims(stft, axis=0)
 
     mag_spectra = spectrum._spectrogram(y=audio, n_fft = int(params[0]),
             win_length=int(params[1]), hop_length=int(params[2]), 
-            power=params[3], window='hann', center=True, pad_mode='reflect')[0]
+            power=params[3], window='hann', center=True, pad_mode='constant')[0]
     if mag_spectra.ndim == 1:
         mag_spectra = np.expand_dims(mag_spectra, axis=1)
     
-    np.savez(filename, audio=audio, params=params, stft=stft, magnitude=mag_spectra)
+    mel_params = get_mel_params(int(params[0]))
+    mel_norm = 'slaney' if mel_params[5] > 0.5 else None
+    mel_filterbank = librosa.filters.mel(sr=mel_params[0], n_fft=int(params[0]),
+            n_mels=int(mel_params[1]), fmin=mel_params[2], fmax=mel_params[3],
+            htk=bool(mel_params[4]), norm=mel_norm)
+    # The FFT sizes are all even so also check the bin frequencies of an odd one
+    odd_mel_filterbank = librosa.filters.mel(sr=mel_params[0], n_fft=int(params[0]) - 1,
+            n_mels=int(mel_params[1]), fmin=mel_params[2], fmax=mel_params[3],
+            htk=bool(mel_params[4]), norm=mel_norm)
+    mel_spectra = librosa.feature.melspectrogram(y=audio, sr=mel_params[0],
+            n_fft=int(params[0]), win_length=int(params[1]), hop_length=int(params[2]),
+            power=params[3], window='hann', center=True, pad_mode='constant',
+            n_mels=int(mel_params[1]), fmin=mel_params[2], fmax=mel_params[3],
+            htk=bool(mel_params[4]), norm=mel_norm)
+
+    mfcc_params = get_mfcc_params()
+    librosa_mfcc = librosa.feature.mfcc(y=audio, sr=mel_params[0], n_mfcc=int(mfcc_params[0]),
+            lifter=int(mfcc_params[1]), n_fft=int(params[0]), win_length=int(params[1]),
+            hop_length=int(params[2]), window='hann', center=True, pad_mode='constant',
+            n_mels=int(mel_params[1]), fmin=mel_params[2], fmax=mel_params[3])
+    psf_mfcc = psf.mfcc(audio, samplerate=mel_params[0], numcep=int(mfcc_params[0]),
+            nfilt=int(mel_params[1]), nfft=int(mfcc_params[2]), lowfreq=mel_params[2],
+            highfreq=mel_params[3], ceplifter=int(mfcc_params[1]))
+    psf_ssc = psf.ssc(audio, samplerate=mel_params[0], nfilt=int(mel_params[1]),
+            nfft=int(mfcc_params[2]), lowfreq=mel_params[2], highfreq=mel_params[3])
+    psf_fbank, psf_energy = psf.fbank(audio, samplerate=mel_params[0],
+            nfilt=int(mel_params[1]), nfft=int(mfcc_params[2]), lowfreq=mel_params[2],
+            highfreq=mel_params[3], winfunc=np.hamming)
+    psf_logfbank = psf.logfbank(audio, samplerate=mel_params[0], nfilt=int(mel_params[1]),
+            nfft=int(mfcc_params[2]), lowfreq=mel_params[2], highfreq=mel_params[3],
+            winfunc=np.hamming)
+    # Every frame is longer than 128 samples so python_speech_features truncates them
+    psf_truncated_fbank, psf_truncated_energy = psf.fbank(audio, samplerate=mel_params[0],
+            nfilt=int(mel_params[1]), nfft=128, lowfreq=mel_params[2],
+            highfreq=mel_params[3], winfunc=np.hamming)
+
+    kaldi_params = get_kaldi_params()
+    kaldi_args = dict(sample_frequency=float(mel_params[0]),
+            frame_length=float(kaldi_params[0]), frame_shift=float(kaldi_params[1]),
+            snip_edges=bool(kaldi_params[2]), preemphasis_coefficient=float(kaldi_params[3]),
+            remove_dc_offset=bool(kaldi_params[4]), raw_energy=bool(kaldi_params[5]),
+            use_energy=bool(kaldi_params[6]), low_freq=float(kaldi_params[7]),
+            high_freq=float(kaldi_params[8]), num_mel_bins=int(kaldi_params[9]),
+            dither=0.0, window_type='povey')
+    waveform = torch.tensor(audio, dtype=torch.float32).unsqueeze(0)
+    kaldi_fbank = kaldi.fbank(waveform, **kaldi_args).numpy()
+    kaldi_mfcc = kaldi.mfcc(waveform, num_ceps=int(kaldi_params[10]), **kaldi_args).numpy()
+
+    delta_params = get_delta_params()
+    delta_mode = ['interp', 'mirror', 'constant', 'nearest', 'wrap'][int(delta_params[2])]
+    librosa_delta = librosa.feature.delta(librosa_mfcc, width=int(delta_params[0]),
+            order=int(delta_params[1]), mode=delta_mode)
+    psf_delta = psf.delta(psf_mfcc, int(delta_params[3]))
+
+    resample_params = get_resample_params(mel_params[0])
+    resampled = resampy.resample(audio, resample_params[0], resample_params[1],
+            filter='kaiser_best' if resample_params[2] > 0.5 else 'kaiser_fast')
+
+    db_params = get_db_params()
+    db_ref = np.max if db_params[0] > 0.5 else db_params[1]
+    top_db = db_params[3] if db_params[3] >= 0.0 else None
+    power_db = librosa.power_to_db(mel_spectra, ref=db_ref, amin=db_params[2], top_db=top_db)
+    amplitude_db = librosa.amplitude_to_db(stft, ref=db_ref, amin=db_params[2], top_db=top_db)
+    db_power = librosa.db_to_power(power_db, ref=db_params[1])
+    db_amplitude = librosa.db_to_amplitude(amplitude_db, ref=db_params[1])
+
+    sr = mel_params[0]
+    spectral_centroid = librosa.feature.spectral_centroid(S=stft, sr=sr)
+    spectral_bandwidth = librosa.feature.spectral_bandwidth(S=stft, sr=sr, p=2)
+    spectral_rolloff = librosa.feature.spectral_rolloff(S=stft, sr=sr, roll_percent=0.85)
+    spectral_flatness = librosa.feature.spectral_flatness(S=stft, power=2.0, amin=1e-10)
+    # 6 bands from 200Hz would pass the nyquist frequency for 8KHz audio
+    spectral_contrast = librosa.feature.spectral_contrast(S=stft, sr=sr, fmin=200.0,
+            n_bands=4, quantile=0.02)
+
+    frame_length = int(params[0])
+    hop_length = int(params[2])
+    rms = librosa.feature.rms(y=audio, frame_length=frame_length, hop_length=hop_length,
+            center=True, pad_mode='constant')
+    spectrogram_rms = librosa.feature.rms(S=stft, frame_length=frame_length)
+    # The audio is all positive so it's shifted to have zero crossings
+    zcr = librosa.feature.zero_crossing_rate(audio - 0.5, frame_length=frame_length,
+            hop_length=hop_length, center=True)
+
+    # Two zero mean sources with estimates that leak the other source and have
+    # added noise
+    bss_references = np.stack((audio - 0.5, npr.rand(audio.size) - 0.5))
+    noise = 0.05 * (npr.rand(*bss_references.shape) - 0.5)
+    bss_estimates = bss_references + 0.2 * bss_references[::-1] + noise
+    bss_sdr, bss_sir, bss_sar, _ = mir_eval.separation.bss_eval_sources(bss_references,
+            bss_estimates, compute_permutation=False)
+
+    np.savez(filename, audio=audio, params=params, stft=stft, magnitude=mag_spectra,
+            mel_params=mel_params, mel_filterbank=mel_filterbank,
+            odd_mel_filterbank=odd_mel_filterbank, melspectrogram=mel_spectra,
+            mfcc_params=mfcc_params, librosa_mfcc=librosa_mfcc, psf_mfcc=psf_mfcc,
+            psf_ssc=psf_ssc, psf_fbank=psf_fbank, psf_energy=psf_energy,
+            psf_logfbank=psf_logfbank,
+            psf_truncated_fbank=psf_truncated_fbank,
+            psf_truncated_energy=psf_truncated_energy, kaldi_params=kaldi_params, kaldi_fbank=kaldi_fbank,
+            kaldi_mfcc=kaldi_mfcc, delta_params=delta_params, librosa_delta=librosa_delta,
+            psf_delta=psf_delta, resample_params=resample_params, resampled=resampled,
+            db_params=db_params, power_db=power_db,
+            amplitude_db=amplitude_db, db_power=db_power, db_amplitude=db_amplitude,
+            spectral_centroid=spectral_centroid, spectral_bandwidth=spectral_bandwidth,
+            spectral_rolloff=spectral_rolloff, spectral_flatness=spectral_flatness,
+            spectral_contrast=spectral_contrast, rms=rms, spectrogram_rms=spectrogram_rms,
+            zcr=zcr, bss_references=bss_references, bss_estimates=bss_estimates,
+            bss_sdr=bss_sdr, bss_sir=bss_sir, bss_sar=bss_sar)
 
 
 